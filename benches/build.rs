@@ -0,0 +1,42 @@
+//! Times building the BSP tree of a generated map of 10,000 walls with each
+//! split heuristic, and with convex leaves.
+//!
+//! Run with `cargo bench --bench build`.
+
+#[path = "../examples/common/mod.rs"]
+mod common;
+
+use std::time::{Duration, Instant};
+
+use bamn::{BuildOptions, SplitHeuristic};
+
+const RUNS: usize = 5;
+
+fn main() {
+    let map = common::rooms(50);
+    println!("{} walls, best of {} runs", map.walls().len(), RUNS);
+
+    let balanced = BuildOptions {
+        heuristic: SplitHeuristic::Balanced,
+        ..BuildOptions::default()
+    };
+    let convex = BuildOptions {
+        convex_leaves: true,
+        ..BuildOptions::default()
+    };
+    for (name, options) in [
+        ("first", BuildOptions::default()),
+        ("balanced", balanced),
+        ("convex leaves", convex),
+    ] {
+        let mut best = Duration::MAX;
+        let mut stats = None;
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            let tree = map.generate_tree_with_options(options).unwrap();
+            best = best.min(start.elapsed());
+            stats = Some(tree.stats());
+        }
+        println!("{:>13}: {:?} {:?}", name, best, stats.unwrap());
+    }
+}
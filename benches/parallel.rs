@@ -0,0 +1,46 @@
+//! Times building the BSP tree of generated maps of several sizes with
+//! [`Map::generate_tree_parallel`](bamn::Map::generate_tree_parallel) against
+//! the sequential build.
+//!
+//! Run with `cargo bench --bench parallel --features rayon`.
+
+#[path = "../examples/common/mod.rs"]
+mod common;
+
+use std::time::{Duration, Instant};
+
+use bamn::BSPTree;
+
+const RUNS: usize = 5;
+
+// the fastest of `RUNS` builds
+fn best(build: impl Fn() -> Option<BSPTree>) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            build().unwrap();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    println!(
+        "best of {} runs on {} threads",
+        RUNS,
+        rayon::current_num_threads()
+    );
+    for side in [10, 25, 50, 100] {
+        let map = common::rooms(side);
+        let sequential = best(|| map.generate_tree());
+        let parallel = best(|| map.generate_tree_parallel());
+        println!(
+            "{:>6} walls: sequential {:?}, parallel {:?}, {:.2}x",
+            map.walls().len(),
+            sequential,
+            parallel,
+            sequential.as_secs_f64() / parallel.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Times finding the render order of a generated map of 40,000 walls from
+//! cameras spread over it, with the boxed tree and with a
+//! [`FlatBSP`](bamn::FlatBSP), copying the walls out against returning their
+//! indices, and prints the bytes each copies per frame.
+//!
+//! Run with `cargo bench --bench render`.
+
+#[path = "../examples/common/mod.rs"]
+mod common;
+
+use std::{
+    mem::size_of,
+    time::{Duration, Instant},
+};
+
+use bamn::{nalgebra::Vector2, FlatBSP, Wall};
+
+const RUNS: usize = 5;
+
+// the fastest of `RUNS` passes over every camera, and the number of items
+// the last pass returned
+fn best(cameras: &[Vector2<f64>], frame: impl Fn(Vector2<f64>) -> usize) -> (Duration, usize) {
+    let mut best = Duration::MAX;
+    let mut items = 0;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        items = cameras.iter().map(|camera| frame(*camera)).sum();
+        best = best.min(start.elapsed());
+    }
+    (best, items)
+}
+
+fn main() {
+    let side = 100;
+    let map = common::rooms(side);
+    let tree = map.generate_tree().unwrap();
+    let flat = FlatBSP::from(&tree);
+    let cameras = (0..10)
+        .flat_map(|x| (0..10).map(move |y| (x, y)))
+        .map(|(x, y)| {
+            Vector2::new(x as f64, y as f64) * side as f64 / 10.0 + Vector2::new(0.5, 0.5)
+        })
+        .collect::<Vec<_>>();
+    println!(
+        "{} walls, {} cameras, best of {} runs",
+        map.walls().len(),
+        cameras.len(),
+        RUNS
+    );
+
+    let report = |name: &str, (time, items): (Duration, usize), size: usize| {
+        println!(
+            "{:>16}: {:?} per frame, {} bytes copied per frame",
+            name,
+            time / cameras.len() as u32,
+            items * size / cameras.len()
+        );
+    };
+    let walls = best(&cameras, |camera| tree.get_render_order(camera).len());
+    report("walls", walls, size_of::<Wall>());
+    let flat_walls = best(&cameras, |camera| flat.render_order(camera).len());
+    report("flat walls", flat_walls, size_of::<Wall>());
+    println!(
+        "{:>16}: {:.2}x",
+        "flat speedup",
+        walls.0.as_secs_f64() / flat_walls.0.as_secs_f64()
+    );
+    let indices = best(&cameras, |camera| {
+        tree.get_render_order_indices(camera).len()
+    });
+    report("indices", indices, size_of::<usize>());
+    let flat_indices = best(&cameras, |camera| flat.render_order_indices(camera).len());
+    report("flat indices", flat_indices, size_of::<usize>());
+}
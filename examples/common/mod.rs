@@ -0,0 +1,36 @@
+//! Map generation shared by the examples and benchmarks.
+
+use bamn::{
+    nalgebra::{Rotation2, Vector2},
+    Map, Wall,
+};
+
+/// A grid of `side` by `side` small rooms, each a square turned by a
+/// pseudo-random angle so few walls share a line. The walls are shuffled so
+/// splitting on the first wall cuts the map roughly in half, which keeps the
+/// tree shallow and gives threads even shares of work.
+pub fn rooms(side: usize) -> Map {
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    };
+    let mut walls = vec![];
+    for x in 0..side {
+        for y in 0..side {
+            let center = Vector2::new(x as f64, y as f64);
+            let turn = Rotation2::new(random() * std::f64::consts::FRAC_PI_2);
+            let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+                .map(|(x, y)| center + turn * Vector2::new(x, y) * 0.3);
+            for i in 0..4 {
+                walls.push(Wall::new(corners[i], corners[(i + 1) % 4]));
+            }
+        }
+    }
+    for i in (1..walls.len()).rev() {
+        walls.swap(i, (random() * (i + 1) as f64) as usize);
+    }
+    Map::from_walls(walls)
+}
@@ -0,0 +1,37 @@
+//! Compares the time to find the render order of a large generated map with
+//! `BSPTree::get_render_order` and `FlatBSP::render_order`.
+//!
+//! Run with `cargo run --release --example flat_render`.
+
+mod common;
+
+use std::time::Instant;
+
+use bamn::{nalgebra::Vector2, FlatBSP};
+
+fn main() {
+    const FRAMES: usize = 20;
+    let map = common::rooms(150);
+    let tree = map.generate_tree().unwrap();
+    let flat = FlatBSP::from(&tree);
+    println!("{} walls, {} frames", flat.walls().len(), FRAMES);
+    let cameras = (0..FRAMES)
+        .map(|i| Vector2::new(i as f64 * 7.3, 150.0 - i as f64 * 5.1))
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    let boxed = cameras
+        .iter()
+        .map(|camera| tree.get_render_order(*camera))
+        .collect::<Vec<_>>();
+    println!("boxed: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let flattened = cameras
+        .iter()
+        .map(|camera| flat.render_order(*camera))
+        .collect::<Vec<_>>();
+    println!("flat:  {:?}", start.elapsed());
+
+    assert_eq!(boxed, flattened);
+}
@@ -0,0 +1,23 @@
+//! Compares the time to build the BSP tree of a large generated map with
+//! `Map::generate_tree` and `Map::generate_tree_parallel`.
+//!
+//! Run with `cargo run --release --example parallel_build --features rayon`.
+
+mod common;
+
+use std::time::Instant;
+
+fn main() {
+    let map = common::rooms(150);
+    println!("{} walls", map.walls().len());
+
+    let start = Instant::now();
+    let sequential = map.generate_tree().unwrap();
+    println!("sequential: {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let parallel = map.generate_tree_parallel().unwrap();
+    println!("parallel:   {:?}", start.elapsed());
+
+    assert_eq!(sequential.describe(), parallel.describe());
+}
@@ -0,0 +1,96 @@
+//! Renders a first-person view of a map file to a PNG, going through the
+//! whole pipeline: reading the map, building its tree, and drawing the walls
+//! of `BSPTree::get_render_order` back to front, each over the ones behind.
+//!
+//! Run with `cargo run --example render_png -- [map] [output]`, which reads
+//! `map.txt` and writes `render.png` by default.
+
+use std::{env, process};
+
+use bamn::{nalgebra::Vector2, Camera, Map};
+use image::{Rgb, RgbImage};
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 200;
+// height of the camera above the floor, halfway up walls of the default height
+const EYE_HEIGHT: f64 = 0.5;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let map_path = args.next().unwrap_or_else(|| "map.txt".to_string());
+    let output = args.next().unwrap_or_else(|| "render.png".to_string());
+
+    let map = Map::from_file(&map_path).unwrap_or_else(|error| {
+        eprintln!("{}: {}", map_path, error);
+        process::exit(1);
+    });
+    let Some(tree) = map.generate_tree() else {
+        eprintln!("{}: no walls to draw", map_path);
+        process::exit(1);
+    };
+    let camera = Camera::new(
+        Vector2::new(-0.6, -0.5),
+        Vector2::new(1.0, 0.5),
+        90f64.to_radians(),
+    );
+
+    let mut image = RgbImage::from_fn(WIDTH, HEIGHT, |_, y| {
+        let ceiling = y < HEIGHT / 2;
+        Rgb(if ceiling { [40, 40, 48] } else { [72, 60, 48] })
+    });
+    let half_width = f64::from(WIDTH) / 2.0;
+    let focal = half_width / (camera.fov / 2.0).tan();
+    let mut drawn = 0;
+    for wall in tree.get_render_order(camera.position) {
+        // two-sided walls are openings, with nothing to draw
+        if wall.two_sided() {
+            continue;
+        }
+        let Some(projected) = wall.project(&camera, WIDTH) else {
+            continue;
+        };
+        let first = projected.left_x.max(0.0).ceil() as u32;
+        let last = projected.right_x.min(f64::from(WIDTH)).ceil() as u32;
+        let span = projected.right_x - projected.left_x;
+        for x in first..last {
+            // the inverse of the depth, not the depth, changes evenly across
+            // the screen, and so does the floor height over the depth
+            let t = if span > 0.0 {
+                (f64::from(x) + 0.5 - projected.left_x) / span
+            } else {
+                0.0
+            };
+            let inverse = (1.0 - t) / projected.left_depth + t / projected.right_depth;
+            let depth = 1.0 / inverse;
+            let floor = ((1.0 - t) * projected.left_floor / projected.left_depth
+                + t * projected.right_floor / projected.right_depth)
+                * depth;
+            let row = |height: f64| f64::from(HEIGHT) / 2.0 - (height - EYE_HEIGHT) * focal / depth;
+            let top = row(wall.ceiling_height()).max(0.0) as u32;
+            let bottom = (row(floor).min(f64::from(HEIGHT))) as u32;
+            let color = shade(wall.texture(), wall.light(), depth);
+            for y in top..bottom {
+                image.put_pixel(x, y, Rgb(color));
+            }
+        }
+        drawn += 1;
+    }
+
+    image.save(&output).unwrap_or_else(|error| {
+        eprintln!("{}: {}", output, error);
+        process::exit(1);
+    });
+    println!("drew {} walls to {}", drawn, output);
+}
+
+// a color for each texture id, darkened by the wall's light and with distance
+fn shade(texture: u32, light: f64, depth: f64) -> [u8; 3] {
+    const PALETTE: [[f64; 3]; 4] = [
+        [200.0, 180.0, 150.0],
+        [150.0, 190.0, 210.0],
+        [210.0, 140.0, 120.0],
+        [150.0, 200.0, 140.0],
+    ];
+    let brightness = light.clamp(0.0, 1.0) / (1.0 + 0.3 * depth);
+    PALETTE[texture as usize % PALETTE.len()].map(|channel| (channel * brightness) as u8)
+}
@@ -0,0 +1,170 @@
+use std::io::{self, Read, Write};
+
+use nalgebra::Vector2;
+
+use crate::{bsp::PreorderNode, BSPTree, Wall};
+
+const MAGIC: &[u8; 4] = b"BSPT";
+// marks a missing sector or child in the node array
+const NONE: u32 = u32::MAX;
+const TWO_SIDED: u32 = 1;
+const UNSORTED: u32 = 1;
+
+impl BSPTree {
+    /// Newest version of the binary format, written by
+    /// [`BSPTree::write_binary`].
+    pub const BINARY_VERSION: u32 = 1;
+
+    /// Writes the tree as the magic bytes `BSPT`, a little-endian `u32` of
+    /// [`BSPTree::BINARY_VERSION`] and a `u32` node count, followed by the
+    /// nodes in preorder. Each node is its segment, a `u32` count of coplanar
+    /// walls and those walls, a `u32` count of further leaf walls and those
+    /// walls, the `u32` number of walls its segment split while building,
+    /// `u32` node flags, then the `u32` indices of its front and behind
+    /// children. Bit 0 of the node flags is set when the leaf walls are the
+    /// unsorted ones left at
+    /// [`BuildOptions::max_depth`](crate::BuildOptions::max_depth).
+    ///
+    /// A wall is its `p1`, `p2` and `forward` as pairs of `f64`, its floor
+    /// and ceiling height, u offset, light and floor slope along x and y as
+    /// `f64`, then its sector, flags, texture and id as `u32`. `u32::MAX`
+    /// stands for a missing sector or child, and bit 0 of the wall flags is
+    /// set for two-sided walls.
+    ///
+    /// Nodes vary in length with their wall counts, so the format has to be
+    /// read front to back and can't be memory-mapped and indexed in place.
+    pub fn write_binary<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let nodes = self.preorder_nodes();
+        w.write_all(MAGIC)?;
+        w.write_all(&Self::BINARY_VERSION.to_le_bytes())?;
+        let count = index_or_none(Some(nodes.len()))?;
+        w.write_all(&count.to_le_bytes())?;
+
+        for node in nodes {
+            write_wall(w, node.segment)?;
+            for walls in [node.coplanar, node.leaf] {
+                w.write_all(&index_or_none(Some(walls.len()))?.to_le_bytes())?;
+                for wall in walls {
+                    write_wall(w, wall)?;
+                }
+            }
+            w.write_all(&index_or_none(Some(node.splits))?.to_le_bytes())?;
+            w.write_all(&if node.unsorted { UNSORTED } else { 0 }.to_le_bytes())?;
+            for child in [node.front, node.behind] {
+                w.write_all(&index_or_none(child)?.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a tree written by [`BSPTree::write_binary`], failing with
+    /// [`io::ErrorKind::InvalidData`] if the input doesn't start with the
+    /// magic bytes or names a version newer than
+    /// [`BSPTree::BINARY_VERSION`].
+    pub fn read_binary<R: Read>(r: &mut R) -> io::Result<BSPTree> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a binary tree"));
+        }
+        let version = read_u32(r)?;
+        if version == 0 || version > Self::BINARY_VERSION {
+            return Err(invalid_data("unsupported binary tree version"));
+        }
+        let count = read_u32(r)? as usize;
+        // the count isn't trusted until that many nodes are read, so a
+        // corrupt header can't reserve gigabytes up front
+        let mut nodes = Vec::with_capacity(count.min(1024));
+        for _ in 0..count {
+            let child = |index: u32| (index != NONE).then_some(index as usize);
+            nodes.push(PreorderNode {
+                segment: read_wall(r)?,
+                coplanar: read_walls(r)?,
+                leaf: read_walls(r)?,
+                splits: read_u32(r)? as usize,
+                unsorted: read_u32(r)? & UNSORTED != 0,
+                front: child(read_u32(r)?),
+                behind: child(read_u32(r)?),
+            });
+        }
+        BSPTree::from_preorder(nodes).map_err(invalid_data)
+    }
+}
+
+fn write_wall<W: Write>(w: &mut W, wall: &Wall) -> io::Result<()> {
+    for value in [
+        wall.p1.x,
+        wall.p1.y,
+        wall.p2.x,
+        wall.p2.y,
+        wall.forward.x,
+        wall.forward.y,
+        wall.floor_height,
+        wall.ceiling_height,
+        wall.u_offset,
+        wall.light,
+        wall.floor_slope.x,
+        wall.floor_slope.y,
+    ] {
+        w.write_all(&value.to_le_bytes())?;
+    }
+    w.write_all(&index_or_none(wall.sector)?.to_le_bytes())?;
+    w.write_all(&if wall.two_sided { TWO_SIDED } else { 0 }.to_le_bytes())?;
+    w.write_all(&wall.texture.to_le_bytes())?;
+    w.write_all(&wall.id.to_le_bytes())
+}
+
+// a `u32` count followed by that many walls
+fn read_walls<R: Read>(r: &mut R) -> io::Result<Vec<Wall>> {
+    (0..read_u32(r)?).map(|_| read_wall(r)).collect()
+}
+
+fn read_wall<R: Read>(r: &mut R) -> io::Result<Wall> {
+    let mut values = [0.0; 12];
+    for value in &mut values {
+        *value = read_f64(r)?;
+    }
+    Ok(Wall {
+        p1: Vector2::new(values[0], values[1]),
+        p2: Vector2::new(values[2], values[3]),
+        forward: Vector2::new(values[4], values[5]),
+        floor_height: values[6],
+        ceiling_height: values[7],
+        u_offset: values[8],
+        light: values[9],
+        floor_slope: Vector2::new(values[10], values[11]),
+        sector: match read_u32(r)? {
+            NONE => None,
+            sector => Some(sector as usize),
+        },
+        two_sided: read_u32(r)? & TWO_SIDED != 0,
+        texture: read_u32(r)?,
+        id: read_u32(r)?,
+    })
+}
+
+fn index_or_none(index: Option<usize>) -> io::Result<u32> {
+    match index {
+        None => Ok(NONE),
+        Some(index) => u32::try_from(index)
+            .ok()
+            .filter(|&index| index != NONE)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "index too large")),
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_f64<R: Read>(r: &mut R) -> io::Result<f64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(f64::from_le_bytes(bytes))
+}
@@ -0,0 +1,794 @@
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::{cmp::Ordering, fmt};
+
+use nalgebra::{Isometry2, Point2, RealField, Vector2};
+
+use crate::{frustum::Frustum, map, rect::ray_reaches_box, Camera, Wall};
+
+/// A node of the BSP tree built by [`Map::generate_tree`](crate::Map::generate_tree).
+///
+/// The fields are private; the tree is queried through its methods. Cloning,
+/// dropping, `Debug` formatting and serializing the tree walk it with an
+/// explicit stack, so trees of any depth can be handled.
+pub struct BSPTree<T = f64> {
+    pub(crate) behind: Box<Option<BSPTree<T>>>,
+    pub(crate) front: Box<Option<BSPTree<T>>>,
+    pub(crate) segment: Wall<T>,
+    // walls lying on the segment's line, which are neither in front of it
+    // nor behind it
+    pub(crate) coplanar: Vec<Wall<T>>,
+    // further walls of a convex leaf, which has no children
+    pub(crate) leaf: Vec<Wall<T>>,
+    // whether `leaf` holds the walls left over at `BuildOptions::max_depth`
+    // instead of a convex set, so they are sorted by distance when rendered
+    pub(crate) unsorted: bool,
+    // number of walls the segment's plane cut in two while building
+    pub(crate) splits: usize,
+    // number of walls in this subtree, the node's own included
+    pub(crate) wall_count: usize,
+    // smallest and largest coordinates of the walls in this subtree
+    pub(crate) bounds: (Vector2<T>, Vector2<T>),
+}
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// The wall whose line splits this node's subtree into its front and
+    /// behind children.
+    ///
+    /// # Examples
+    ///
+    /// Walking the children of the root of a tree:
+    ///
+    /// ```
+    /// use bamn::{nalgebra::Vector2, Map, Wall};
+    ///
+    /// let map = Map::from_walls(vec![
+    ///     Wall::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0)),
+    ///     Wall::new(Vector2::new(0.0, 1.0), Vector2::new(1.0, 1.0)),
+    ///     Wall::new(Vector2::new(0.0, -1.0), Vector2::new(1.0, -1.0)),
+    /// ]);
+    /// let tree = map.generate_tree().unwrap();
+    /// assert_eq!(tree.segment().p1(), Vector2::new(0.0, 0.0));
+    ///
+    /// let front = tree.front().unwrap();
+    /// assert_eq!(front.segment().p1(), Vector2::new(0.0, 1.0));
+    /// assert!(front.front().is_none() && front.behind().is_none());
+    ///
+    /// let behind = tree.behind().unwrap();
+    /// assert_eq!(behind.segment().p1(), Vector2::new(0.0, -1.0));
+    /// ```
+    pub fn segment(&self) -> &Wall<T> {
+        &self.segment
+    }
+
+    /// The subtree in front of [`BSPTree::segment`].
+    pub fn front(&self) -> Option<&BSPTree<T>> {
+        self.front.as_ref().as_ref()
+    }
+
+    /// The subtree behind [`BSPTree::segment`].
+    pub fn behind(&self) -> Option<&BSPTree<T>> {
+        self.behind.as_ref().as_ref()
+    }
+
+    /// The corners of the axis-aligned box around every wall in this subtree,
+    /// smallest coordinates first. Queries skip subtrees whose box they
+    /// don't reach.
+    pub fn bounds(&self) -> (Vector2<T>, Vector2<T>) {
+        self.bounds
+    }
+
+    /// Walls ordered back to front as seen from `camera_pos`.
+    pub fn get_render_order(&self, camera_pos: Vector2<T>) -> Vec<Wall<T>> {
+        let mut out = vec![];
+
+        self.get_render_order_into(camera_pos, &mut out);
+
+        out
+    }
+
+    /// Like [`BSPTree::get_render_order`], but leaves out the one-sided
+    /// walls that don't [face](Wall::faces) the camera, whose backs a
+    /// renderer of one-sided walls wouldn't draw. Two-sided walls are always
+    /// kept.
+    pub fn get_render_order_culled(&self, camera_pos: Vector2<T>) -> Vec<Wall<T>> {
+        let mut out = vec![];
+        Self::walk_render_order(self, camera_pos, true, None, |wall, _| {
+            if wall.two_sided || wall.faces(camera_pos) {
+                out.push(*wall);
+            }
+            true
+        });
+        out
+    }
+
+    /// Like [`BSPTree::get_render_order`], but clears `out` and writes the
+    /// walls into it, so a buffer kept between frames is reused instead of
+    /// allocating a new one each frame.
+    pub fn get_render_order_into(&self, camera_pos: Vector2<T>, out: &mut Vec<Wall<T>>) {
+        out.clear();
+        Self::get_render_walls(self, out, camera_pos, true, None);
+    }
+
+    /// Like [`BSPTree::get_render_order`], but with each wall given by its
+    /// index in [`BSPTree::iter`] instead of copied out of the tree. Collect
+    /// the walls of `iter` into a `Vec` once after building or editing the
+    /// tree to look them up, and each frame only copies a `usize` per wall.
+    /// A [`FlatBSP`](crate::FlatBSP) stores its walls in such an array; see
+    /// [`FlatBSP::render_order_indices`](crate::FlatBSP::render_order_indices).
+    ///
+    /// The indices are into the tree's own storage order, not positions in
+    /// the walls it was built from: building reorders the walls and each
+    /// piece of a split wall has its own index. Use the [id](Wall::id) of the
+    /// stored wall to find the map wall it came from.
+    pub fn get_render_order_indices(&self, camera_pos: Vector2<T>) -> Vec<usize> {
+        let mut out = vec![];
+        Self::walk_render_order(self, camera_pos, true, None, |_, index| {
+            out.push(index);
+            true
+        });
+        out
+    }
+
+    /// Walls ordered front to back as seen from `camera_pos`, the reverse of
+    /// [`BSPTree::get_render_order`].
+    pub fn get_render_order_front_to_back(&self, camera_pos: Vector2<T>) -> Vec<Wall<T>> {
+        let mut out = vec![];
+
+        Self::get_render_walls(self, &mut out, camera_pos, false, None);
+
+        out
+    }
+
+    /// Like [`BSPTree::get_render_order`], but keeps only the `max` walls
+    /// that come first front to back, dropping the rest, to bound the walls
+    /// drawn in a frame. No dropped wall can hide a kept one, so the kept
+    /// walls are the nearest in view. The walk stops once `max` walls are
+    /// found.
+    pub fn get_render_order_limited(&self, camera_pos: Vector2<T>, max: usize) -> Vec<Wall<T>> {
+        let mut out = Vec::with_capacity(max.min(self.wall_count));
+        if max > 0 {
+            Self::walk_render_order(self, camera_pos, false, None, |wall, _| {
+                out.push(*wall);
+                out.len() < max
+            });
+        }
+        out.reverse();
+        out
+    }
+
+    /// Like [`BSPTree::get_render_order`], for a copy of the tree placed in
+    /// the world by `instance`, which maps the tree's own coordinates to
+    /// world coordinates. `camera_pos` is in world coordinates and is moved
+    /// into the tree's by the inverse of `instance`, so many instances can
+    /// share one tree. The walls are returned in the tree's coordinates;
+    /// apply `instance` to them to place them in the world.
+    pub fn get_render_order_instanced(
+        &self,
+        camera_pos: Vector2<T>,
+        instance: &Isometry2<T>,
+    ) -> Vec<Wall<T>> {
+        let local = instance.inverse_transform_point(&Point2::from(camera_pos));
+        self.get_render_order(local.coords)
+    }
+
+    /// Like [`BSPTree::get_render_order`], but only the walls at least partly
+    /// inside the view cone of `fov_radians` around `facing_dir`. Subtrees on
+    /// a side of a split the cone doesn't reach are skipped entirely.
+    pub fn get_render_order_fov(
+        &self,
+        camera_pos: Vector2<T>,
+        facing_dir: Vector2<T>,
+        fov_radians: T,
+    ) -> Vec<Wall<T>> {
+        let mut out = vec![];
+        let frustum = Frustum::new(camera_pos, facing_dir, fov_radians);
+
+        Self::get_render_walls(self, &mut out, camera_pos, true, Some(&frustum));
+
+        out
+    }
+
+    /// Walls inside the view of `camera`, ordered back to front.
+    pub fn get_render_order_for_camera(&self, camera: &Camera<T>) -> Vec<Wall<T>> {
+        let mut out = vec![];
+
+        Self::get_render_walls(
+            self,
+            &mut out,
+            camera.position,
+            true,
+            Some(&camera.frustum()),
+        );
+
+        out
+    }
+
+    /// The nearest wall hit by the ray from `origin` along `dir`, and the point
+    /// where it is hit.
+    ///
+    /// Nodes are visited near to far from `origin`, so the first wall hit is
+    /// the nearest one and the search stops there. Subtrees on the far side of
+    /// a split the ray never crosses, or whose [bounds](BSPTree::bounds) the
+    /// ray misses, are skipped.
+    pub fn raycast(&self, origin: Vector2<T>, dir: Vector2<T>) -> Option<(Vector2<T>, Wall<T>)> {
+        let mut stack = vec![Visit::Node(self, 0)];
+        while let Some(visit) = stack.pop() {
+            let node = match visit {
+                Visit::Segment(segment, _) => {
+                    if let Some(t) = segment.ray_intersection(origin, dir) {
+                        return Some((origin + dir * t, *segment));
+                    }
+                    continue;
+                }
+                Visit::Node(node, _) => node,
+            };
+            let origin_in_front = node.segment.in_front_point(&origin);
+            let heading = dir.dot(&node.segment.forward);
+            let (near, far) = if origin_in_front {
+                (&node.front, &node.behind)
+            } else {
+                (&node.behind, &node.front)
+            };
+            let crosses = if origin_in_front {
+                heading < T::zero()
+            } else {
+                heading > T::zero()
+            };
+            let reaches = |child: &&BSPTree<T>| ray_reaches_box(origin, dir, child.bounds);
+            if crosses {
+                if let Some(far) = far.as_ref().as_ref().filter(reaches) {
+                    stack.push(Visit::Node(far, 0));
+                }
+            }
+            if node.unsorted {
+                // the walls of the leaf are in no useful order, so only the
+                // nearest one hit is visited
+                let hit = node
+                    .walls()
+                    .filter_map(|wall| Some((wall.ray_intersection(origin, dir)?, wall)))
+                    .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+                stack.extend(hit.map(|(_, wall)| Visit::Segment(wall, 0)));
+            } else {
+                node.push_walls(&mut stack, 0, origin, false, None);
+            }
+            if let Some(near) = near.as_ref().as_ref().filter(reaches) {
+                stack.push(Visit::Node(near, 0));
+            }
+        }
+        None
+    }
+
+    /// The splitting walls bounding the convex region that contains `point`,
+    /// from the root down to the leaf.
+    ///
+    /// At each node the walk continues into the front subtree if `point` is
+    /// in front of the node's segment and into the behind subtree otherwise,
+    /// stopping once that subtree is empty. Points on the same side of every
+    /// returned wall are in the same region.
+    pub fn locate(&self, point: Vector2<T>) -> Vec<&Wall<T>> {
+        let mut out = vec![];
+        let mut node = Some(self);
+        while let Some(current) = node {
+            out.push(&current.segment);
+            node = if current.segment.in_front_point(&point) {
+                current.front()
+            } else {
+                current.behind()
+            };
+        }
+        out
+    }
+
+    /// Whether the straight line from `a` to `b` crosses no one-sided wall
+    /// segment.
+    ///
+    /// Only subtrees on a side of a split that the line reaches are searched,
+    /// and the search stops at the first blocking wall.
+    pub fn line_of_sight(&self, a: Vector2<T>, b: Vector2<T>) -> bool {
+        let sight = Wall::new(a, b);
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            let blocked = node
+                .walls()
+                .any(|wall| !wall.two_sided && sight.intersection_segment(wall).is_some());
+            if blocked {
+                return false;
+            }
+            let a_front = node.segment.in_front_point(&a);
+            let b_front = node.segment.in_front_point(&b);
+            if let Some(front) = node.front.as_ref() {
+                if a_front || b_front {
+                    stack.push(front);
+                }
+            }
+            if let Some(behind) = node.behind.as_ref() {
+                if !a_front || !b_front {
+                    stack.push(behind);
+                }
+            }
+        }
+        true
+    }
+
+    /// Every wall stored in the tree, visited in order: a node's behind
+    /// subtree, then its own walls, then its front subtree. Nothing is
+    /// allocated beyond a stack as deep as the tree.
+    pub fn iter(&self) -> impl Iterator<Item = &Wall<T>> {
+        let mut stack = vec![Visit::Node(self, 0)];
+        core::iter::from_fn(move || {
+            while let Some(visit) = stack.pop() {
+                match visit {
+                    Visit::Segment(wall, _) => return Some(wall),
+                    Visit::Node(node, _) => {
+                        if let Some(front) = node.front.as_ref() {
+                            stack.push(Visit::Node(front, 0));
+                        }
+                        for wall in node.leaf.iter().rev().chain(node.coplanar.iter().rev()) {
+                            stack.push(Visit::Segment(wall, 0));
+                        }
+                        stack.push(Visit::Segment(&node.segment, 0));
+                        if let Some(behind) = node.behind.as_ref() {
+                            stack.push(Visit::Node(behind, 0));
+                        }
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    /// Indented dump of the tree, one node per line with its depth, segment
+    /// and the number of nodes in each subtree, under a header line with the
+    /// node count and maximum depth.
+    pub fn describe(&self) -> String {
+        let nodes = self.preorder();
+        let children = Self::preorder_children(&nodes);
+        let sizes = Self::subtree_sizes(&children);
+
+        let max_depth = nodes.iter().map(|(depth, _)| *depth).max().unwrap_or(0);
+        let mut out = format!("nodes={} max_depth={}\n", nodes.len(), max_depth);
+        for (i, (depth, node)) in nodes.iter().enumerate() {
+            let [front, behind] = children[i].map(|child| child.map_or(0, |c| sizes[c]));
+            let segment = node.segment;
+            out += &format!(
+                "{}[d={}] ({:?},{:?})->({:?},{:?}) front={} behind={}{}\n",
+                "  ".repeat(*depth),
+                depth,
+                segment.p1.x,
+                segment.p1.y,
+                segment.p2.x,
+                segment.p2.y,
+                front,
+                behind,
+                [
+                    ("coplanar", node.coplanar.len()),
+                    (
+                        if node.unsorted { "unsorted" } else { "leaf" },
+                        node.leaf.len()
+                    )
+                ]
+                .iter()
+                .filter(|(_, count)| *count > 0)
+                .map(|(name, count)| format!(" {}={}", name, count))
+                .collect::<String>()
+            );
+        }
+        out
+    }
+
+    // the tree laid out as by `preorder_nodes`, which only needs each node's
+    // children to come after it and every node but the first to have one
+    // parent
+    #[cfg(any(feature = "std", feature = "serde"))]
+    pub(crate) fn from_preorder(
+        nodes: Vec<PreorderNode<Wall<T>, Vec<Wall<T>>>>,
+    ) -> Result<BSPTree<T>, &'static str> {
+        let count = nodes.len();
+        if count == 0 {
+            return Err("tree has no nodes");
+        }
+        // assembled from the back, so each node's children are built before
+        // it without recursing
+        let mut built: Vec<Option<BSPTree<T>>> = (0..count).map(|_| None).collect();
+        for (i, node) in nodes.into_iter().enumerate().rev() {
+            let mut child = |index: Option<usize>| {
+                let Some(index) = index else {
+                    return Ok(None);
+                };
+                if index <= i || index >= count {
+                    return Err("child index out of range");
+                }
+                built[index]
+                    .take()
+                    .map(Some)
+                    .ok_or("node has more than one parent")
+            };
+            let front = child(node.front)?;
+            let behind = child(node.behind)?;
+            built[i] = Some(BSPTree::node(
+                node.segment,
+                node.coplanar,
+                node.leaf,
+                node.unsorted,
+                node.splits,
+                front,
+                behind,
+            ));
+        }
+        if built[1..].iter().any(Option::is_some) {
+            return Err("node has no parent");
+        }
+        Ok(built[0].take().expect("the first node has no parent"))
+    }
+
+    // a node with `front` and `behind` as its children, counting its walls
+    pub(crate) fn node(
+        segment: Wall<T>,
+        coplanar: Vec<Wall<T>>,
+        leaf: Vec<Wall<T>>,
+        unsorted: bool,
+        splits: usize,
+        front: Option<BSPTree<T>>,
+        behind: Option<BSPTree<T>>,
+    ) -> BSPTree<T> {
+        let count = |child: &Option<BSPTree<T>>| child.as_ref().map_or(0, |c| c.wall_count);
+        let walls = core::iter::once(&segment).chain(&coplanar).chain(&leaf);
+        let bounds = [&front, &behind]
+            .into_iter()
+            .flatten()
+            .fold(map::bounds(walls), |a, child| join(a, child.bounds));
+        BSPTree {
+            wall_count: 1 + coplanar.len() + leaf.len() + count(&front) + count(&behind),
+            bounds,
+            behind: Box::new(behind),
+            front: Box::new(front),
+            segment,
+            coplanar,
+            leaf,
+            unsorted,
+            splits,
+        }
+    }
+
+    // counts the walls of every subtree and finds their bounds again after
+    // the tree was edited in place
+    pub(crate) fn recount(&mut self) {
+        let counts = {
+            let nodes = self.preorder();
+            let children = Self::preorder_children(&nodes);
+            let mut counts = vec![(0, (Vector2::zeros(), Vector2::zeros())); nodes.len()];
+            for i in (0..nodes.len()).rev() {
+                let node = nodes[i].1;
+                counts[i] = (
+                    1 + node.coplanar.len() + node.leaf.len(),
+                    map::bounds(node.walls()),
+                );
+                for child in children[i].into_iter().flatten() {
+                    let (count, bounds) = counts[child];
+                    counts[i].0 += count;
+                    counts[i].1 = join(counts[i].1, bounds);
+                }
+            }
+            counts
+        };
+        // visited in the same order as `preorder`
+        let mut stack = vec![self];
+        let mut counts = counts.into_iter();
+        while let Some(node) = stack.pop() {
+            (node.wall_count, node.bounds) = counts
+                .next()
+                .unwrap_or((0, (Vector2::zeros(), Vector2::zeros())));
+            if let Some(behind) = node.behind.as_mut() {
+                stack.push(behind);
+            }
+            if let Some(front) = node.front.as_mut() {
+                stack.push(front);
+            }
+        }
+    }
+
+    // the segment, coplanar and leaf walls stored at this node
+    pub(crate) fn walls(&self) -> impl Iterator<Item = &Wall<T>> {
+        core::iter::once(&self.segment)
+            .chain(&self.coplanar)
+            .chain(&self.leaf)
+    }
+
+    // pushes the walls stored at this node so they pop in render order,
+    // numbered from `first` in the order of `walls`; in a convex leaf the
+    // walls the camera sees from the front are further away than the ones it
+    // sees from behind, and neither group overlaps itself
+    fn push_walls<'a>(
+        &'a self,
+        stack: &mut Vec<Visit<'a, T>>,
+        first: usize,
+        camera_pos: Vector2<T>,
+        back_to_front: bool,
+        frustum: Option<&Frustum<T>>,
+    ) {
+        let visible = self
+            .walls()
+            .enumerate()
+            .filter(|(_, wall)| frustum.is_none_or(|f| f.intersects(wall)))
+            .map(|(i, wall)| (wall, first + i));
+        let visit = |(wall, index)| Visit::Segment(wall, index);
+        // walls on one line can't hide one another
+        if self.leaf.is_empty() {
+            stack.extend(visible.map(visit));
+            return;
+        }
+        let mut order = if self.unsorted {
+            // farthest first, which is right wherever the walls don't overlap
+            // on screen
+            let mut order = visible
+                .map(|(wall, index)| (wall.distance_to_point(camera_pos), wall, index))
+                .collect::<Vec<_>>();
+            order.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+            order
+                .into_iter()
+                .map(|(_, wall, index)| (wall, index))
+                .collect()
+        } else {
+            let (mut order, mut near): (Vec<_>, Vec<_>) =
+                visible.partition(|(wall, _)| wall.in_front_point(&camera_pos));
+            order.append(&mut near);
+            order
+        };
+        // `order` is back to front and the stack pops it reversed
+        if back_to_front {
+            order.reverse();
+        }
+        stack.extend(order.into_iter().map(visit));
+    }
+
+    fn get_render_walls(
+        root: &BSPTree<T>,
+        out: &mut Vec<Wall<T>>,
+        camera_pos: Vector2<T>,
+        back_to_front: bool,
+        frustum: Option<&Frustum<T>>,
+    ) {
+        Self::walk_render_order(root, camera_pos, back_to_front, frustum, |wall, _| {
+            out.push(*wall);
+            true
+        });
+    }
+
+    // calls `emit` with the walls of the tree in render order, each with its
+    // index in `iter`, until it returns false
+    pub(crate) fn walk_render_order<'a>(
+        root: &'a BSPTree<T>,
+        camera_pos: Vector2<T>,
+        back_to_front: bool,
+        frustum: Option<&Frustum<T>>,
+        mut emit: impl FnMut(&'a Wall<T>, usize) -> bool,
+    ) {
+        // an explicit stack keeps deep, degenerate trees from overflowing the
+        // call stack
+        let mut stack = vec![Visit::Node(root, 0)];
+        while let Some(visit) = stack.pop() {
+            let (node, base) = match visit {
+                Visit::Segment(segment, index) => {
+                    if !emit(segment, index) {
+                        return;
+                    }
+                    continue;
+                }
+                Visit::Node(node, base) => (node, base),
+            };
+            // `iter` goes through the behind subtree, the node's own walls,
+            // then the front subtree
+            let first = base + node.behind().map_or(0, |b| b.wall_count);
+            let front_base = first + 1 + node.coplanar.len() + node.leaf.len();
+            let front = (node.front.as_ref(), true, front_base);
+            let behind = (node.behind.as_ref(), false, base);
+            let (near, last) = if node.segment.in_front_point(&camera_pos) != back_to_front {
+                (front, behind)
+            } else {
+                (behind, front)
+            };
+            let reaches = |child: &BSPTree<T>, side: bool| {
+                frustum.is_none_or(|f| {
+                    f.reaches_side(&node.segment, side) && f.reaches_box(child.bounds)
+                })
+            };
+            // pushed in reverse so they pop as near, node walls, last
+            if let (Some(last), side, base) = last {
+                if reaches(last, side) {
+                    stack.push(Visit::Node(last, base));
+                }
+            }
+            node.push_walls(&mut stack, first, camera_pos, back_to_front, frustum);
+            if let (Some(near), side, base) = near {
+                if reaches(near, side) {
+                    stack.push(Visit::Node(near, base));
+                }
+            }
+        }
+    }
+}
+
+impl<T> BSPTree<T> {
+    // nodes with their depth, each node followed by its front then behind
+    // subtree
+    pub(crate) fn preorder(&self) -> Vec<(usize, &BSPTree<T>)> {
+        let mut out = vec![];
+        let mut stack = vec![(0, self)];
+        while let Some((depth, node)) = stack.pop() {
+            out.push((depth, node));
+            if let Some(behind) = node.behind.as_ref() {
+                stack.push((depth + 1, behind));
+            }
+            if let Some(front) = node.front.as_ref() {
+                stack.push((depth + 1, front));
+            }
+        }
+        out
+    }
+
+    // the indices into `preorder` of each node's front and behind children.
+    // In preorder a node's subtree directly follows it, front first
+    pub(crate) fn preorder_children(nodes: &[(usize, &BSPTree<T>)]) -> Vec<[Option<usize>; 2]> {
+        let mut sizes = vec![0; nodes.len()];
+        let mut children = vec![[None; 2]; nodes.len()];
+        for i in (0..nodes.len()).rev() {
+            let node = nodes[i].1;
+            let front = node.front.is_some().then_some(i + 1);
+            let behind = node
+                .behind
+                .is_some()
+                .then(|| i + 1 + front.map_or(0, |f| sizes[f]));
+            children[i] = [front, behind];
+            sizes[i] = 1 + children[i]
+                .iter()
+                .flatten()
+                .map(|c| sizes[*c])
+                .sum::<usize>();
+        }
+        children
+    }
+
+    // number of nodes in the subtree rooted at each node of a `preorder`,
+    // given the `preorder_children` of its nodes
+    pub(crate) fn subtree_sizes(children: &[[Option<usize>; 2]]) -> Vec<usize> {
+        let mut sizes = vec![0; children.len()];
+        for i in (0..children.len()).rev() {
+            sizes[i] = 1 + children[i]
+                .iter()
+                .flatten()
+                .map(|c| sizes[*c])
+                .sum::<usize>();
+        }
+        sizes
+    }
+
+    // the nodes in preorder, with their children as indices into it
+    pub(crate) fn preorder_nodes(&self) -> Vec<PreorderNode<&Wall<T>, &[Wall<T>]>> {
+        let nodes = self.preorder();
+        let children = Self::preorder_children(&nodes);
+        nodes
+            .iter()
+            .zip(children)
+            .map(|((_, node), [front, behind])| PreorderNode {
+                segment: &node.segment,
+                coplanar: &node.coplanar[..],
+                leaf: &node.leaf[..],
+                unsorted: node.unsorted,
+                splits: node.splits,
+                front,
+                behind,
+            })
+            .collect()
+    }
+}
+
+/// Copies the nodes one at a time from an explicit stack.
+impl<T: Clone> Clone for BSPTree<T> {
+    fn clone(&self) -> Self {
+        let copy = |node: &BSPTree<T>| BSPTree {
+            behind: Box::new(None),
+            front: Box::new(None),
+            segment: node.segment.clone(),
+            coplanar: node.coplanar.clone(),
+            leaf: node.leaf.clone(),
+            unsorted: node.unsorted,
+            splits: node.splits,
+            wall_count: node.wall_count,
+            bounds: node.bounds.clone(),
+        };
+        let mut root = copy(self);
+        let mut stack = vec![(&mut root, self)];
+        while let Some((out, node)) = stack.pop() {
+            if let Some(front) = node.front.as_ref() {
+                *out.front = Some(copy(front));
+                stack.extend(out.front.as_mut().as_mut().map(|out| (out, front)));
+            }
+            if let Some(behind) = node.behind.as_ref() {
+                *out.behind = Some(copy(behind));
+                stack.extend(out.behind.as_mut().as_mut().map(|out| (out, behind)));
+            }
+        }
+        root
+    }
+}
+
+/// Takes the children out of each node before it is dropped, so no drop
+/// recurses into the subtree below it.
+impl<T> Drop for BSPTree<T> {
+    fn drop(&mut self) {
+        let mut stack = vec![];
+        stack.extend(self.front.take());
+        stack.extend(self.behind.take());
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.front.take());
+            stack.extend(node.behind.take());
+        }
+    }
+}
+
+/// Formats the tree as its nodes in preorder, each with the indices of its
+/// front and behind children in that order, instead of nesting them.
+impl<T: fmt::Debug> fmt::Debug for BSPTree<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BSPTree")
+            .field("nodes", &self.preorder_nodes())
+            .finish()
+    }
+}
+
+/// Serialized as a struct whose `nodes` are the nodes in preorder, each with
+/// the indices of its front and behind children in that order.
+#[cfg(feature = "serde")]
+impl<T: RealField + Copy + serde::Serialize> serde::Serialize for BSPTree<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut tree = serializer.serialize_struct("BSPTree", 1)?;
+        tree.serialize_field("nodes", &self.preorder_nodes())?;
+        tree.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: RealField + Copy + serde::Deserialize<'de>> serde::Deserialize<'de> for BSPTree<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(bound(deserialize = "T: RealField + Copy + serde::Deserialize<'de>"))]
+        struct Tree<T: RealField + Copy> {
+            nodes: Vec<PreorderNode<Wall<T>, Vec<Wall<T>>>>,
+        }
+        let tree = Tree::deserialize(deserializer)?;
+        BSPTree::from_preorder(tree.nodes).map_err(serde::de::Error::custom)
+    }
+}
+
+// a node of a tree laid out in preorder, with its children as indices into
+// the layout, holding its walls as `W` and lists of walls as `L`
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+// only `Debug` reads the fields without the binary format or serde
+#[cfg_attr(not(any(feature = "std", feature = "serde")), allow(dead_code))]
+pub(crate) struct PreorderNode<W, L> {
+    pub(crate) segment: W,
+    pub(crate) coplanar: L,
+    pub(crate) leaf: L,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) unsorted: bool,
+    pub(crate) splits: usize,
+    pub(crate) front: Option<usize>,
+    pub(crate) behind: Option<usize>,
+}
+
+// the box around both boxes
+fn join<T: RealField + Copy>(
+    a: (Vector2<T>, Vector2<T>),
+    b: (Vector2<T>, Vector2<T>),
+) -> (Vector2<T>, Vector2<T>) {
+    (a.0.inf(&b.0), a.1.sup(&b.1))
+}
+
+// nodes carry the index in `iter` of the first wall of their subtree, and
+// walls their own index, where the walk keeps track of them
+enum Visit<'a, T> {
+    Node(&'a BSPTree<T>, usize),
+    Segment(&'a Wall<T>, usize),
+}
@@ -0,0 +1,387 @@
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{wall::tolerance, BSPTree, Map, Wall};
+
+/// Options for [`Map::generate_tree_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BuildOptions {
+    pub heuristic: SplitHeuristic,
+    /// Stop splitting once the remaining walls bound a convex region, each in
+    /// front of every other's plane, and store them together in one leaf.
+    pub convex_leaves: bool,
+    /// Depth below the root at which to stop splitting, to bound the size of
+    /// the tree on maps that would need many splits. A node at this depth
+    /// keeps every wall left in a leaf list, which is sorted by distance from
+    /// the camera when rendered. The order within such a leaf is only
+    /// approximate where its walls overlap on screen.
+    pub max_depth: Option<usize>,
+    /// Tolerance for deciding where walls lie while building, `1e-9` if
+    /// `None`. A wall within this fraction of the longer wall's length of a
+    /// splitting line counts as lying on it, and a wall crossing the line
+    /// within this fraction of its length from an end isn't split. Both are
+    /// relative, so maps at any scale build alike; lower it to keep walls
+    /// that nearly line up apart, or raise it for maps whose coordinates
+    /// were rounded.
+    pub epsilon: Option<f64>,
+}
+
+impl BuildOptions {
+    // the tolerance `epsilon` stands for
+    pub(crate) fn tolerance(&self) -> f64 {
+        self.epsilon.unwrap_or(1e-9)
+    }
+}
+
+impl<T: RealField + Copy> Map<T> {
+    /// Builds the BSP tree of the map. Zero-length walls have no plane to
+    /// split by and are left out of the tree; check for them with
+    /// [`Map::validate`] first, or build with
+    /// [`Map::generate_tree_with_report`] to see which were left out.
+    pub fn generate_tree(&self) -> Option<BSPTree<T>> {
+        self.generate_tree_with(SplitHeuristic::default())
+    }
+
+    pub fn generate_tree_with(&self, heuristic: SplitHeuristic) -> Option<BSPTree<T>> {
+        self.generate_tree_with_options(BuildOptions {
+            heuristic,
+            ..BuildOptions::default()
+        })
+    }
+
+    pub fn generate_tree_with_options(&self, options: BuildOptions) -> Option<BSPTree<T>> {
+        self.generate_tree_profiled(options, &mut |_: usize, _: bool| {})
+    }
+
+    /// Like [`Map::generate_tree_with_options`], calling `profiler` for each
+    /// node in the order the nodes are built, to see where build time goes.
+    pub fn generate_tree_profiled(
+        &self,
+        options: BuildOptions,
+        profiler: &mut impl BuildProfiler,
+    ) -> Option<BSPTree<T>> {
+        let walls = self
+            .walls
+            .iter()
+            .filter(|wall| !wall.is_degenerate())
+            .copied()
+            .collect::<Vec<Wall<T>>>();
+        tree_create_with(&walls, options, |_, _| {}, profiler)
+    }
+
+    /// Like [`Map::generate_tree`], but splits on the walls in a fixed
+    /// geometric order instead of the order they appear in the map, so maps
+    /// holding the same walls in any order build the same tree. The order
+    /// only makes the result reproducible, and can give a deeper tree than
+    /// the map's own order or [`SplitHeuristic::Balanced`].
+    pub fn generate_tree_sorted(&self) -> Option<BSPTree<T>> {
+        let mut walls = self
+            .walls
+            .iter()
+            .filter(|wall| !wall.is_degenerate())
+            .copied()
+            .collect::<Vec<Wall<T>>>();
+        walls.sort_by(geometric_order);
+        tree_create(&walls, BuildOptions::default())
+    }
+}
+
+/// Receives a call for each node built by [`Map::generate_tree_profiled`],
+/// such as to gather statistics for tuning [`BuildOptions`]. Closures taking
+/// the same arguments as [`BuildProfiler::node`] are profilers.
+pub trait BuildProfiler {
+    /// A node was built from `walls` walls, the walls left to place in its
+    /// subtree before any are cut further down, and `spliced` is whether
+    /// splitting on its segment cut any of them in two. Leaves don't split,
+    /// so they are never `spliced`.
+    fn node(&mut self, walls: usize, spliced: bool);
+}
+
+impl<F: FnMut(usize, bool)> BuildProfiler for F {
+    fn node(&mut self, walls: usize, spliced: bool) {
+        self(walls, spliced);
+    }
+}
+
+// orders walls by their lower then upper endpoint, then by direction,
+// texture, two-sidedness, sector and light
+fn geometric_order<T: RealField + Copy>(a: &Wall<T>, b: &Wall<T>) -> Ordering {
+    let key = |wall: &Wall<T>| {
+        let reversed = (wall.p2.x, wall.p2.y) < (wall.p1.x, wall.p1.y);
+        let (low, high) = if reversed {
+            (wall.p2, wall.p1)
+        } else {
+            (wall.p1, wall.p2)
+        };
+        ([low.x, low.y, high.x, high.y], reversed)
+    };
+    let ((a_points, a_reversed), (b_points, b_reversed)) = (key(a), key(b));
+    a_points
+        .iter()
+        .zip(&b_points)
+        .map(|(a, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+        .then(a_reversed.cmp(&b_reversed))
+        .then(a.texture.cmp(&b.texture))
+        .then(a.two_sided.cmp(&b.two_sided))
+        .then(a.sector.cmp(&b.sector))
+        .then(a.light.partial_cmp(&b.light).unwrap_or(Ordering::Equal))
+}
+
+pub(crate) fn tree_create<T: RealField + Copy>(
+    walls: &[Wall<T>],
+    options: BuildOptions,
+) -> Option<BSPTree<T>> {
+    tree_create_with(walls, options, |_, _| {}, &mut |_: usize, _: bool| {})
+}
+
+// builds the tree, calling `stored` with each wall placed in a node and the
+// index in `walls` of the wall it was spliced from, and `profiler` with each
+// node
+pub(crate) fn tree_create_with<T: RealField + Copy>(
+    walls: &[Wall<T>],
+    options: BuildOptions,
+    mut stored: impl FnMut(usize, &Wall<T>),
+    profiler: &mut impl BuildProfiler,
+) -> Option<BSPTree<T>> {
+    if walls.is_empty() {
+        return None;
+    }
+    // nodes are built from an explicit work stack rather than by recursing,
+    // so deep trees can't overflow the call stack; each entry holds the
+    // child slot of its parent to fill in and where its walls start in
+    // `pending`, whose tail always holds the walls of the topmost entry.
+    // `origins` holds the input index of each pending wall
+    let mut nodes: Vec<Node<T>> = vec![];
+    let mut pending = walls.to_vec();
+    let mut origins = (0..walls.len()).collect::<Vec<usize>>();
+    let mut spliced = vec![];
+    let mut work = vec![(None::<(usize, bool)>, 0, 0)];
+    while let Some((parent, start, depth)) = work.pop() {
+        let index = nodes.len();
+        if let Some((parent, front)) = parent {
+            let parent = &mut nodes[parent];
+            if front {
+                parent.front = Some(index);
+            } else {
+                parent.behind = Some(index);
+            }
+        }
+
+        let walls = &pending[start..];
+        let convex =
+            walls.len() == 1 || (options.convex_leaves && is_convex(walls, options.tolerance()));
+        if convex || options.max_depth == Some(depth) {
+            profiler.node(walls.len(), false);
+            for (wall, origin) in walls.iter().zip(&origins[start..]) {
+                stored(*origin, wall);
+            }
+            nodes.push(Node {
+                segment: walls[0],
+                coplanar: vec![],
+                leaf: walls[1..].to_vec(),
+                unsorted: !convex,
+                splits: 0,
+                front: None,
+                behind: None,
+            });
+            pending.truncate(start);
+            origins.truncate(start);
+            continue;
+        }
+        let split = options.heuristic.choose(walls, options.tolerance());
+        let slice_plane = walls[split];
+        stored(origins[start + split], &slice_plane);
+
+        spliced.clear();
+        let mut coplanar = vec![];
+        let splits = place_walls(
+            walls,
+            split,
+            options.tolerance(),
+            |i, wall, place| match place {
+                Place::Coplanar => {
+                    stored(origins[start + i], &wall);
+                    coplanar.push(wall);
+                }
+                Place::Front => spliced.push((wall, origins[start + i], true)),
+                Place::Behind => spliced.push((wall, origins[start + i], false)),
+            },
+        );
+        profiler.node(walls.len(), splits > 0);
+        pending.truncate(start);
+        origins.truncate(start);
+
+        // front walls then back walls, so the back side is on top and is
+        // built first
+        for &(wall, origin, _) in spliced.iter().filter(|(_, _, front)| *front) {
+            pending.push(wall);
+            origins.push(origin);
+        }
+        let back_start = pending.len();
+        for &(wall, origin, _) in spliced.iter().filter(|(_, _, front)| !*front) {
+            pending.push(wall);
+            origins.push(origin);
+        }
+
+        nodes.push(Node {
+            segment: slice_plane,
+            coplanar,
+            leaf: vec![],
+            unsorted: false,
+            splits,
+            front: None,
+            behind: None,
+        });
+        if back_start > start {
+            work.push((Some((index, true)), start, depth + 1));
+        }
+        if pending.len() > back_start {
+            work.push((Some((index, false)), back_start, depth + 1));
+        }
+    }
+
+    // children are always pushed after their parent, so the trees can be
+    // assembled from the back
+    let mut built: Vec<Option<BSPTree<T>>> = (0..nodes.len()).map(|_| None).collect();
+    for (i, node) in nodes.into_iter().enumerate().rev() {
+        let mut child = |index: Option<usize>| index.and_then(|index| built[index].take());
+        built[i] = Some(BSPTree::node(
+            node.segment,
+            node.coplanar,
+            node.leaf,
+            node.unsorted,
+            node.splits,
+            child(node.front),
+            child(node.behind),
+        ));
+    }
+    built[0].take()
+}
+
+// where a wall, or a piece of one, goes at the node splitting on a plane
+#[derive(Clone, Copy)]
+pub(crate) enum Place {
+    Coplanar,
+    Front,
+    Behind,
+}
+
+// calls `place` with each wall of `walls` other than the one at `split`, or
+// with both pieces in order where the split's plane cuts it, along with its
+// index in `walls`, and returns the number of walls cut. Walls on the plane
+// within `epsilon` stay at the node, and the rest are spliced where their
+// endpoints are on opposite sides of it
+pub(crate) fn place_walls<T: RealField + Copy>(
+    walls: &[Wall<T>],
+    split: usize,
+    epsilon: f64,
+    mut place: impl FnMut(usize, Wall<T>, Place),
+) -> usize {
+    let plane = walls[split];
+    // every wall is measured against the same plane, so its normal and
+    // length are found once rather than for each wall; the tests match
+    // `on_plane`, `split_point` and `in_front`
+    let normal = plane.normal();
+    let distance = |point: Vector2<T>| (point - plane.p1).dot(&normal);
+    let (length, tolerance) = (plane.length(), tolerance::<T>(epsilon));
+    let side = |wall: &Wall<T>| {
+        if distance(wall.midpoint()) > T::zero() {
+            Place::Front
+        } else {
+            Place::Behind
+        }
+    };
+    let mut splits = 0;
+    for (i, wall) in walls.iter().enumerate().filter(|(i, _)| *i != split) {
+        let (d1, d2) = (distance(wall.p1), distance(wall.p2));
+        let limit = tolerance * length.max(wall.length());
+        if d1.abs() <= limit && d2.abs() <= limit {
+            place(i, *wall, Place::Coplanar);
+        } else if let Some(intersection) = wall.split_point_at(d1, d2, epsilon) {
+            let halves = wall.splice(intersection);
+            splits += 1;
+            place(i, halves.0, side(&halves.0));
+            place(i, halves.1, side(&halves.1));
+        } else {
+            place(i, *wall, side(wall));
+        }
+    }
+    splits
+}
+
+// a node of the tree under construction, with its children as indices into
+// the list of nodes
+struct Node<T> {
+    segment: Wall<T>,
+    coplanar: Vec<Wall<T>>,
+    leaf: Vec<Wall<T>>,
+    unsorted: bool,
+    splits: usize,
+    front: Option<usize>,
+    behind: Option<usize>,
+}
+
+// whether every wall lies in front of, or within `epsilon` of, the plane of
+// every other
+pub(crate) fn is_convex<T: RealField + Copy>(walls: &[Wall<T>], epsilon: f64) -> bool {
+    walls.iter().all(|plane| {
+        walls.iter().all(|wall| {
+            [wall.p1, wall.p2].iter().all(|point| {
+                plane.signed_distance(*point)
+                    >= -tolerance::<T>(epsilon) * (point - plane.p1).norm()
+            })
+        })
+    })
+}
+
+/// How the splitting wall is picked at each node of the BSP tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SplitHeuristic {
+    /// Split on the first remaining wall, so the tree follows the order of the
+    /// walls in the map.
+    #[default]
+    First,
+    /// Split on the wall that minimizes a weighted sum of the walls it would
+    /// splice and the size difference between its front and back sides.
+    Balanced,
+}
+
+impl SplitHeuristic {
+    // weighing splices more heavily trades depth for fewer walls
+    const SPLIT_WEIGHT: usize = 1;
+
+    pub(crate) fn choose<T: RealField + Copy>(self, walls: &[Wall<T>], epsilon: f64) -> usize {
+        match self {
+            SplitHeuristic::First => 0,
+            SplitHeuristic::Balanced => (0..walls.len())
+                .min_by_key(|&candidate| Self::score(walls, candidate, epsilon))
+                .unwrap_or(0),
+        }
+    }
+
+    fn score<T: RealField + Copy>(walls: &[Wall<T>], candidate: usize, epsilon: f64) -> usize {
+        let plane = walls[candidate];
+        let mut splits = 0;
+        let mut front = 0usize;
+        let mut back = 0;
+        for (i, wall) in walls.iter().enumerate() {
+            if i == candidate {
+                continue;
+            }
+            if wall.split_point(&plane, epsilon).is_some() {
+                splits += 1;
+                front += 1;
+                back += 1;
+            } else if plane.in_front(wall) {
+                front += 1;
+            } else {
+                back += 1;
+            }
+        }
+        splits * Self::SPLIT_WEIGHT + front.abs_diff(back)
+    }
+}
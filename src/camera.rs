@@ -0,0 +1,52 @@
+use nalgebra::{convert, RealField, Rotation2, Scalar, Vector2};
+
+use crate::frustum::Frustum;
+
+/// A viewpoint looking along `facing` with a horizontal field of view of
+/// `fov` radians. `facing` need not be normalized. Nothing closer than `near`
+/// along the view direction is projected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Camera<T: Scalar = f64> {
+    pub position: Vector2<T>,
+    pub facing: Vector2<T>,
+    pub fov: T,
+    pub near: T,
+}
+
+impl Camera {
+    pub const DEFAULT_NEAR: f64 = 0.01;
+}
+
+impl<T: RealField + Copy> Camera<T> {
+    pub fn new(position: Vector2<T>, facing: Vector2<T>, fov: T) -> Camera<T> {
+        Camera {
+            position,
+            facing,
+            fov,
+            near: convert(Camera::DEFAULT_NEAR),
+        }
+    }
+
+    /// Unit vector along `facing`, or +x if `facing` is zero.
+    pub fn direction(&self) -> Vector2<T> {
+        self.facing
+            .try_normalize(T::zero())
+            .unwrap_or_else(Vector2::x)
+    }
+
+    /// Unit vector pointing to the right of the view direction.
+    pub fn right(&self) -> Vector2<T> {
+        let direction = self.direction();
+        Vector2::new(direction.y, -direction.x)
+    }
+
+    /// Turns the camera counterclockwise by `radians`.
+    pub fn rotate(&mut self, radians: T) {
+        self.facing = Rotation2::new(radians) * self.facing;
+    }
+
+    pub(crate) fn frustum(&self) -> Frustum<T> {
+        Frustum::new(self.position, self.facing, self.fov)
+    }
+}
@@ -0,0 +1,76 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{BSPTree, Wall};
+
+// corrections from walls meeting at an acute angle can push the circle back
+// into a wall already resolved, so the walls are revisited a few times
+const PASSES: usize = 4;
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// Moves a circle of `radius` at `pos` out of every one-sided wall of the
+    /// tree it overlaps and returns its new center. Two-sided walls are
+    /// portals and let the circle through.
+    ///
+    /// Each pass looks up the walls within `radius` of where the circle is
+    /// at its start, so walls the circle was pushed towards by an earlier
+    /// pass are found too.
+    pub fn resolve_collision(&self, mut pos: Vector2<T>, radius: T) -> Vector2<T> {
+        for _ in 0..PASSES {
+            if !push_out(self.walls_within(pos, radius), &mut pos, radius) {
+                break;
+            }
+        }
+        pos
+    }
+
+    /// Walls of the tree closer than `radius` to `point`, skipping subtrees
+    /// on the far side of a split whose plane is at least `radius` away.
+    pub fn walls_within(&self, point: Vector2<T>, radius: T) -> Vec<&Wall<T>> {
+        let mut out = vec![];
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            out.extend(
+                node.walls()
+                    .filter(|wall| wall.distance_to_point(point) < radius),
+            );
+            let plane = &node.segment;
+            let offset = plane.signed_distance(point);
+            if let Some(front) = node.front.as_ref() {
+                if offset > -radius {
+                    stack.push(front);
+                }
+            }
+            if let Some(behind) = node.behind.as_ref() {
+                if offset < radius {
+                    stack.push(behind);
+                }
+            }
+        }
+        out
+    }
+}
+
+// moves the circle of `radius` at `pos` out of each of `walls` it overlaps
+// in turn, returning whether it moved
+fn push_out<T: RealField + Copy>(walls: Vec<&Wall<T>>, pos: &mut Vector2<T>, radius: T) -> bool {
+    let mut moved = false;
+    for wall in walls {
+        if wall.two_sided || wall.is_degenerate() {
+            continue;
+        }
+        let closest = wall.closest_point(*pos);
+        let distance = (*pos - closest).norm();
+        if distance >= radius {
+            continue;
+        }
+        // a center exactly on the wall is pushed out of its front side
+        let away = (*pos - closest)
+            .try_normalize(T::zero())
+            .unwrap_or_else(|| wall.normal());
+        *pos += away * (radius - distance);
+        moved = true;
+    }
+    moved
+}
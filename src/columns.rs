@@ -0,0 +1,124 @@
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+
+use nalgebra::{convert, RealField};
+
+use crate::{BSPTree, Camera, Wall};
+
+/// The screen columns drawn so far while rendering front to back, kept as
+/// sorted runs so a fully covered screen can be detected cheaply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnBuffer {
+    width: u32,
+    filled: Vec<Range<u32>>,
+}
+
+impl ColumnBuffer {
+    pub fn new(width: u32) -> ColumnBuffer {
+        ColumnBuffer {
+            width,
+            filled: vec![],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn is_filled(&self, column: u32) -> bool {
+        self.filled.iter().any(|run| run.contains(&column))
+    }
+
+    /// Whether every column of the screen is filled.
+    pub fn is_full(&self) -> bool {
+        self.filled.first() == Some(&(0..self.width)) || self.width == 0
+    }
+
+    /// The runs of columns in `columns` not filled yet, left to right.
+    pub fn unfilled(&self, columns: Range<u32>) -> Vec<Range<u32>> {
+        let columns = columns.start.min(self.width)..columns.end.min(self.width);
+        let mut out = vec![];
+        let mut start = columns.start;
+        for run in &self.filled {
+            if run.end <= start {
+                continue;
+            }
+            if run.start >= columns.end {
+                break;
+            }
+            if run.start > start {
+                out.push(start..run.start);
+            }
+            start = run.end;
+        }
+        if start < columns.end {
+            out.push(start..columns.end);
+        }
+        out
+    }
+
+    /// Fills the columns in `columns` and returns the runs of them that were
+    /// not filled before, as [`ColumnBuffer::unfilled`].
+    pub fn fill(&mut self, columns: Range<u32>) -> Vec<Range<u32>> {
+        let out = self.unfilled(columns.clone());
+        if out.is_empty() {
+            return out;
+        }
+        // merge every run touching the new one into it
+        let (mut start, mut end) = (out[0].start, out[out.len() - 1].end);
+        self.filled.retain(|run| {
+            let touches = run.start <= end && start <= run.end;
+            if touches {
+                start = start.min(run.start);
+                end = end.max(run.end);
+            }
+            !touches
+        });
+        let index = self.filled.partition_point(|run| run.start < start);
+        self.filled.insert(index, start..end);
+        out
+    }
+}
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// The screen columns of a `width` column wide view from `camera` that
+    /// each wall is drawn in, nearest wall first.
+    ///
+    /// Walls are projected front to back into a [`ColumnBuffer`], and each
+    /// wall is listed once for every run of its columns no nearer one-sided
+    /// wall has filled. A wall in view whose columns are all filled is listed
+    /// once with an empty range. Two-sided walls are portals, so they fill
+    /// no columns. The walk stops as soon as every column is filled.
+    pub fn render_columns(&self, camera: &Camera<T>, width: u32) -> Vec<(Wall<T>, Range<u32>)> {
+        let mut buffer = ColumnBuffer::new(width);
+        let mut out = vec![];
+        let column = |x: T| {
+            // a column is covered when its center is
+            let x = (x - convert(0.5)).ceil().to_subset().unwrap_or(0.0);
+            x.clamp(0.0, f64::from(width)) as u32
+        };
+        Self::walk_render_order(
+            self,
+            camera.position,
+            false,
+            Some(&camera.frustum()),
+            |wall, _| {
+                let Some(projected) = wall.project(camera, width) else {
+                    return true;
+                };
+                let columns = column(projected.left_x)..column(projected.right_x);
+                let runs = if wall.two_sided {
+                    buffer.unfilled(columns.clone())
+                } else {
+                    buffer.fill(columns.clone())
+                };
+                if runs.is_empty() {
+                    out.push((*wall, columns.start..columns.start));
+                }
+                out.extend(runs.into_iter().map(|run| (*wall, run)));
+                !buffer.is_full()
+            },
+        );
+        out
+    }
+}
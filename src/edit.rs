@@ -0,0 +1,201 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::RealField;
+
+use crate::{build::tree_create, wall::tolerance, BSPTree, BuildOptions, Wall};
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// Adds `wall` to the tree without rebuilding it. The wall is passed down
+    /// the tree and spliced by the splits it crosses, and each piece becomes a
+    /// new leaf where it runs out of tree, or joins a split it lies on. A
+    /// convex leaf the wall reaches is rebuilt with the wall added, and a
+    /// leaf left unsorted at [`BuildOptions::max_depth`] just takes it. The
+    /// tree is not rebalanced, so many inserts can leave it deeper than a
+    /// rebuild would.
+    ///
+    /// Walls are placed with the default options; see
+    /// [`BSPTree::insert_wall_with_options`].
+    pub fn insert_wall(&mut self, wall: Wall<T>) {
+        self.insert_wall_with_options(wall, BuildOptions::default());
+    }
+
+    /// [`BSPTree::insert_wall`], placing the wall with the tolerance of
+    /// `options` and rebuilding leaves with them, which should be the ones
+    /// the tree was built with. A leaf at [`BuildOptions::max_depth`]
+    /// becomes an unsorted leaf instead of growing children below it.
+    pub fn insert_wall_with_options(&mut self, wall: Wall<T>, options: BuildOptions) {
+        if wall.is_degenerate() {
+            return;
+        }
+        let epsilon = options.tolerance();
+        let mut stack = vec![(&mut *self, wall, 0)];
+        while let Some((node, wall, depth)) = stack.pop() {
+            if node.unsorted {
+                node.leaf.push(wall);
+                continue;
+            }
+            let at_limit = options.max_depth.is_some_and(|max| depth >= max);
+            if !node.leaf.is_empty() || at_limit {
+                let mut walls = node.iter().copied().collect::<Vec<_>>();
+                walls.push(wall);
+                let options = BuildOptions {
+                    convex_leaves: options.convex_leaves || !node.leaf.is_empty(),
+                    max_depth: options.max_depth.map(|max| max.saturating_sub(depth)),
+                    ..options
+                };
+                if let Some(tree) = tree_create(&walls, options) {
+                    *node = tree;
+                }
+                continue;
+            }
+
+            let plane = node.segment;
+            if wall.on_plane(&plane, epsilon) {
+                node.coplanar.push(wall);
+                continue;
+            }
+            let (mut front, mut behind) = (None, None);
+            let pieces = match wall.split_point(&plane, epsilon) {
+                Some(point) => {
+                    let (first, second) = wall.splice(point);
+                    node.splits += 1;
+                    [Some(first), Some(second)]
+                }
+                None => [Some(wall), None],
+            };
+            for piece in pieces.into_iter().flatten() {
+                if plane.in_front(&piece) {
+                    front = Some(piece);
+                } else {
+                    behind = Some(piece);
+                }
+            }
+
+            for (child, piece) in [(&mut node.front, front), (&mut node.behind, behind)] {
+                let Some(piece) = piece else {
+                    continue;
+                };
+                if child.is_none() {
+                    **child = Some(BSPTree::node(piece, vec![], vec![], false, 0, None, None));
+                    continue;
+                }
+                if let Some(child) = child.as_mut() {
+                    stack.push((child, piece, depth + 1));
+                }
+            }
+        }
+        self.recount();
+    }
+
+    /// Removes `wall` from the tree, along with every piece it was spliced
+    /// into, and returns whether anything was removed. A piece is any stored
+    /// wall with the same [id](Wall::id) as `wall` that lies on it pointing
+    /// the same way, so another wall with its own id along the same line is
+    /// kept.
+    ///
+    /// Subtrees whose splitting wall is removed are rebuilt from their
+    /// remaining walls with the default options; see
+    /// [`BSPTree::remove_wall_with_options`]. The tree can't be left empty,
+    /// so if every wall in it is a piece of `wall` nothing is removed and
+    /// `false` is returned.
+    pub fn remove_wall(&mut self, wall: &Wall<T>) -> bool {
+        self.remove_wall_with_options(wall, BuildOptions::default())
+    }
+
+    /// [`BSPTree::remove_wall`], rebuilding subtrees with `options`, which
+    /// should be the ones the tree was built with. A rebuilt subtree keeps
+    /// to what is left of [`BuildOptions::max_depth`] below it.
+    pub fn remove_wall_with_options(&mut self, wall: &Wall<T>, options: BuildOptions) -> bool {
+        if !self.iter().any(|stored| is_piece(stored, wall)) {
+            return false;
+        }
+        if is_piece(&self.segment, wall) {
+            return match rebuild_without(self, wall, options, 0) {
+                Some(tree) => {
+                    *self = tree;
+                    true
+                }
+                None => false,
+            };
+        }
+
+        let (children, cut) = cuts(self, wall);
+        let mut stack = vec![(&mut *self, 0, 0)];
+        while let Some((node, i, depth)) = stack.pop() {
+            if cut[i] {
+                node.splits = node.splits.saturating_sub(1);
+            }
+            node.coplanar.retain(|stored| !is_piece(stored, wall));
+            node.leaf.retain(|stored| !is_piece(stored, wall));
+            let [front, behind] = children[i];
+            for (slot, child) in [(&mut *node.front, front), (&mut *node.behind, behind)] {
+                if let Some(node) = slot.as_ref().filter(|node| is_piece(&node.segment, wall)) {
+                    *slot = rebuild_without(node, wall, options, depth + 1);
+                    continue;
+                }
+                if let (Some(node), Some(child)) = (slot.as_mut(), child) {
+                    stack.push((node, child, depth + 1));
+                }
+            }
+        }
+        self.recount();
+        true
+    }
+}
+
+// the children of each node of `tree` in preorder, and whether the node's
+// plane split `wall`, which it did if pieces of it lie on both sides. Pieces
+// on one side were split from a single piece reaching the node, as a convex
+// region holds only one stretch of a line
+fn cuts<T: RealField + Copy>(
+    tree: &BSPTree<T>,
+    wall: &Wall<T>,
+) -> (Vec<[Option<usize>; 2]>, Vec<bool>) {
+    let nodes = tree.preorder();
+    let children = BSPTree::preorder_children(&nodes);
+    let mut holds = vec![false; nodes.len()];
+    let mut cut = vec![false; nodes.len()];
+    for i in (0..nodes.len()).rev() {
+        let [front, behind] = children[i].map(|child| child.is_some_and(|c| holds[c]));
+        cut[i] = front && behind;
+        holds[i] = front || behind || nodes[i].1.walls().any(|stored| is_piece(stored, wall));
+    }
+    (children, cut)
+}
+
+// whether `piece` was cut from `wall`: it has the same id, and lies on
+// `wall` pointing the same way in case ids were reused
+fn is_piece<T: RealField + Copy>(piece: &Wall<T>, wall: &Wall<T>) -> bool {
+    let tolerance = tolerance::<T>(1e-9) * wall.length();
+    piece.id == wall.id
+        && wall.distance_to_point(piece.p1) <= tolerance
+        && wall.distance_to_point(piece.p2) <= tolerance
+        && (piece.p2 - piece.p1).dot(&(wall.p2 - wall.p1)) > T::zero()
+}
+
+// `node`'s subtree, `depth` below the root, built again from the walls that
+// aren't pieces of `wall`
+fn rebuild_without<T: RealField + Copy>(
+    node: &BSPTree<T>,
+    wall: &Wall<T>,
+    options: BuildOptions,
+    depth: usize,
+) -> Option<BSPTree<T>> {
+    let walls = node
+        .iter()
+        .filter(|stored| !is_piece(stored, wall))
+        .copied()
+        .collect::<Vec<_>>();
+    // an unsorted leaf is built into one again, and any other subtree keeps
+    // to the depth left below it
+    let options = BuildOptions {
+        convex_leaves: options.convex_leaves || !node.leaf.is_empty(),
+        max_depth: if node.unsorted {
+            Some(0)
+        } else {
+            options.max_depth.map(|max| max.saturating_sub(depth))
+        },
+        ..options
+    };
+    tree_create(&walls, options)
+}
@@ -0,0 +1,636 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, Seek, Write},
+    time::SystemTime,
+};
+
+use nalgebra::Vector2;
+
+use crate::{Map, Sector, Thing, Wall, Winding};
+
+/// Error returned by [`Map::from_file`] and [`Map::from_reader`]. Line numbers
+/// start at 1.
+#[derive(Debug)]
+pub enum MapParseError {
+    Open(io::Error),
+    Read {
+        line: usize,
+        error: io::Error,
+    },
+    InvalidNumber {
+        line: usize,
+        token: String,
+    },
+    /// A line with a number of fields outside those its section accepts in
+    /// the file's version, from `min` up to `max`, or with no upper limit if
+    /// `max` is `None`.
+    FieldCount {
+        line: usize,
+        min: usize,
+        max: Option<usize>,
+        found: usize,
+    },
+    IndexOutOfRange {
+        line: usize,
+        index: usize,
+    },
+    UnsupportedVersion {
+        line: usize,
+        version: u32,
+    },
+    /// A field or section added in a later format version than the one the
+    /// file declares.
+    NeedsVersion {
+        line: usize,
+        version: u32,
+    },
+    /// A coordinate or height is infinite or NaN.
+    NonFinite {
+        line: usize,
+    },
+    /// A token that can't appear where it does.
+    Unexpected {
+        line: usize,
+        token: String,
+    },
+    /// The input ended inside a block that was never closed.
+    UnexpectedEnd,
+}
+
+impl fmt::Display for MapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapParseError::Open(error) => write!(f, "could not open map file: {}", error),
+            MapParseError::Read { line, error } => {
+                write!(f, "line {}: could not read line: {}", line, error)
+            }
+            MapParseError::InvalidNumber { line, token } => {
+                write!(f, "line {}: invalid number {:?}", line, token)
+            }
+            MapParseError::FieldCount {
+                line,
+                min,
+                max,
+                found,
+            } => {
+                write!(f, "line {}: expected ", line)?;
+                match max {
+                    Some(max) if max == min => write!(f, "{}", min)?,
+                    Some(max) => write!(f, "{} to {}", min, max)?,
+                    None => write!(f, "at least {}", min)?,
+                }
+                write!(f, " fields, found {}", found)
+            }
+            MapParseError::IndexOutOfRange { line, index } => {
+                write!(f, "line {}: index {} is out of range", line, index)
+            }
+            MapParseError::UnsupportedVersion { line, version } => {
+                write!(f, "line {}: unsupported format version {}", line, version)
+            }
+            MapParseError::NeedsVersion { line, version } => {
+                write!(
+                    f,
+                    "line {}: needs format version {} or later",
+                    line, version
+                )
+            }
+            MapParseError::NonFinite { line } => {
+                write!(f, "line {}: number is not finite", line)
+            }
+            MapParseError::Unexpected { line, token } => {
+                write!(f, "line {}: unexpected {:?}", line, token)
+            }
+            MapParseError::UnexpectedEnd => write!(f, "unexpected end of input"),
+        }
+    }
+}
+
+/// Read errors keep their own kind, and the rest become
+/// [`io::ErrorKind::InvalidData`].
+impl From<MapParseError> for io::Error {
+    fn from(error: MapParseError) -> io::Error {
+        match error {
+            MapParseError::Open(error) | MapParseError::Read { error, .. } => error,
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
+
+impl std::error::Error for MapParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MapParseError::Open(error) | MapParseError::Read { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn parse_token<T: std::str::FromStr>(
+    token: &str,
+    line_number: usize,
+) -> Result<T, MapParseError> {
+    token
+        .parse::<T>()
+        .map_err(|_| MapParseError::InvalidNumber {
+            line: line_number,
+            token: token.to_string(),
+        })
+}
+
+fn parse_fields<T: std::str::FromStr>(
+    line: &str,
+    line_number: usize,
+    expected: usize,
+) -> Result<Vec<T>, MapParseError> {
+    let fields = line
+        .split_whitespace()
+        .map(|x| parse_token(x, line_number))
+        .collect::<Result<Vec<T>, MapParseError>>()?;
+    if fields.len() != expected {
+        return Err(MapParseError::FieldCount {
+            line: line_number,
+            min: expected,
+            max: Some(expected),
+            found: fields.len(),
+        });
+    }
+    Ok(fields)
+}
+
+// infinities and NaNs parse as numbers, but no tree can be built from them
+pub(crate) fn finite(values: &[f64], line_number: usize) -> Result<(), MapParseError> {
+    if values.iter().all(|value| value.is_finite()) {
+        Ok(())
+    } else {
+        Err(MapParseError::NonFinite { line: line_number })
+    }
+}
+
+// the file's modification time, for `Map::reload_if_changed`
+fn modified(file: &File) -> Option<SystemTime> {
+    file.metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+// the number of lines in the walls sections, without parsing them
+fn count_walls<R: BufRead>(reader: R) -> Result<usize, MapParseError> {
+    let mut count = 0;
+    let mut walls = false;
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|error| MapParseError::Read {
+            line: line_number + 1,
+            error,
+        })?;
+        match line.trim() {
+            "walls" => walls = true,
+            "verticies" | "vertices" | "sectors" | "things" | "slopes" => walls = false,
+            line if walls && !line.is_empty() && !line.starts_with('#') => count += 1,
+            _ => {}
+        }
+    }
+    Ok(count)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Verticies,
+    Walls,
+    Sectors,
+    Things,
+    Slopes,
+}
+
+impl Map {
+    pub fn from_file(path: &str) -> Result<Map, MapParseError> {
+        let file = File::open(path).map_err(MapParseError::Open)?;
+        let modified = modified(&file);
+        let map = Self::from_reader(BufReader::new(file))?;
+        Ok(Map { modified, ..map })
+    }
+
+    /// Like [`Map::from_file`], but reads the file twice, first counting its
+    /// walls and then loading them, and calls `progress` with the number of
+    /// walls read so far and the total after each wall, for a progress bar
+    /// that knows how far along it is.
+    pub fn from_file_with_progress(
+        path: &str,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<Map, MapParseError> {
+        let mut file = File::open(path).map_err(MapParseError::Open)?;
+        let total = count_walls(BufReader::new(&file))?;
+        file.rewind().map_err(MapParseError::Open)?;
+        let modified = modified(&file);
+        let map =
+            Self::from_reader_with_progress(BufReader::new(file), |read| progress(read, total))?;
+        Ok(Map { modified, ..map })
+    }
+
+    /// Reads the map from `path` again if the file's modification time has
+    /// changed since this map was last read from a file, and returns whether
+    /// it did, so an editor can pick up saved edits without rebuilding its
+    /// tree every frame. A map not read by [`Map::from_file`] or this method
+    /// is always read.
+    ///
+    /// If the changed file can't be parsed, the map is left as it was and
+    /// the error is returned; the file isn't read again until it changes
+    /// once more.
+    pub fn reload_if_changed(&mut self, path: &str) -> io::Result<bool> {
+        let modified = std::fs::metadata(path)?.modified()?;
+        if self.modified == Some(modified) {
+            return Ok(false);
+        }
+        self.modified = Some(modified);
+        let map = Self::from_file(path)?;
+        *self = Map {
+            modified: map.modified.or(Some(modified)),
+            ..map
+        };
+        Ok(true)
+    }
+
+    /// Newest version of the map format, written by [`Map::write`].
+    pub const FORMAT_VERSION: u32 = 6;
+
+    /// Reads a map. The file may start with a `version N` line naming the
+    /// format version it is written in, and is read as version 1 without it.
+    /// Fields on a line are separated by any run of spaces or tabs.
+    /// Sections start with a `verticies` (or `vertices`), `walls`, `sectors`,
+    /// `things` or `slopes` line and may come in any order, with lines before the first
+    /// header read as vertices; wall and vertex indices are resolved once the
+    /// whole file is read.
+    /// Version 2 adds the header to version 1 and changes nothing else,
+    /// version 3 adds the optional light level of each wall, version 4
+    /// writes a header before the vertices too, version 5 adds the `things`
+    /// section, and version 6 adds the `slopes` section, whose lines give
+    /// the index of a sector from 1 and its [floor slope](Sector::floor_slope)
+    /// along x and y; later versions may add sections that older ones
+    /// reject. A field or section from a later version than the file declares
+    /// is an error.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Map, MapParseError> {
+        Self::from_reader_with_progress(reader, |_| {})
+    }
+
+    /// Like [`Map::from_reader`], but calls `progress` with the number of
+    /// walls read so far after each wall, for example to drive a progress
+    /// bar while loading a large map. The reader is read once, so the total
+    /// isn't known; [`Map::from_file_with_progress`] counts it first.
+    pub fn from_reader_with_progress<R: BufRead>(
+        reader: R,
+        progress: impl FnMut(usize),
+    ) -> Result<Map, MapParseError> {
+        Self::read(reader, Winding::default(), progress)
+    }
+
+    /// Like [`Map::from_reader`], for maps whose walls face the side that
+    /// `winding` names. [`Map::write`] does not record the winding, so read a
+    /// written map back with the same one.
+    pub fn from_reader_with_winding<R: BufRead>(
+        reader: R,
+        winding: Winding,
+    ) -> Result<Map, MapParseError> {
+        Self::read(reader, winding, |_| {})
+    }
+
+    fn read<R: BufRead>(
+        reader: R,
+        winding: Winding,
+        mut progress: impl FnMut(usize),
+    ) -> Result<Map, MapParseError> {
+        let mut out = Map::from_walls(vec![]);
+        let mut verticies = vec![];
+        // walls and sectors refer to vertices and walls that may come later,
+        // so they keep their line and indices until the end
+        let mut walls = vec![];
+        let mut sectors = vec![];
+        let mut slopes = vec![];
+        let mut section = Section::Verticies;
+        // `None` until the first line that isn't blank or a comment
+        let mut version = None;
+        for (line_number, line) in reader.lines().enumerate() {
+            let line_number = line_number + 1;
+            let line = line.map_err(|error| MapParseError::Read {
+                line: line_number,
+                error,
+            })?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if version.is_none() {
+                if let Some(token) = line
+                    .strip_prefix("version")
+                    .filter(|rest| rest.starts_with(char::is_whitespace))
+                {
+                    let number = parse_token::<u32>(token.trim_start(), line_number)?;
+                    if number == 0 || number > Self::FORMAT_VERSION {
+                        return Err(MapParseError::UnsupportedVersion {
+                            line: line_number,
+                            version: number,
+                        });
+                    }
+                    version = Some(number);
+                    continue;
+                }
+                version = Some(1);
+            }
+            let needs = |needed: u32| {
+                if version < Some(needed) {
+                    Err(MapParseError::NeedsVersion {
+                        line: line_number,
+                        version: needed,
+                    })
+                } else {
+                    Ok(())
+                }
+            };
+            match line {
+                "verticies" | "vertices" => {
+                    needs(4)?;
+                    section = Section::Verticies;
+                    continue;
+                }
+                "walls" => {
+                    section = Section::Walls;
+                    continue;
+                }
+                "sectors" => {
+                    section = Section::Sectors;
+                    continue;
+                }
+                "things" => {
+                    needs(5)?;
+                    section = Section::Things;
+                    continue;
+                }
+                "slopes" => {
+                    needs(6)?;
+                    section = Section::Slopes;
+                    continue;
+                }
+                _ => {}
+            }
+            match section {
+                Section::Verticies => {
+                    let coordinates = parse_fields::<f64>(line, line_number, 2)?;
+                    finite(&coordinates, line_number)?;
+                    verticies.push(Vector2::new(coordinates[0], coordinates[1]));
+                }
+                // two vertex indices, optionally followed by 1 for a
+                // two-sided wall, a texture id and a light level
+                Section::Walls => {
+                    let fields = line.split_whitespace().collect::<Vec<&str>>();
+                    if fields.len() < 2 || fields.len() > 5 {
+                        // the light level came in version 3
+                        let max = if version < Some(3) { 4 } else { 5 };
+                        return Err(MapParseError::FieldCount {
+                            line: line_number,
+                            min: 2,
+                            max: Some(max),
+                            found: fields.len(),
+                        });
+                    }
+                    let indexs = [
+                        parse_token::<usize>(fields[0], line_number)?,
+                        parse_token::<usize>(fields[1], line_number)?,
+                    ];
+                    // holds the optional fields until the endpoints are known
+                    let mut wall = Wall::new(Vector2::zeros(), Vector2::zeros());
+                    if let Some(token) = fields.get(2) {
+                        wall.two_sided = match *token {
+                            "0" => false,
+                            "1" => true,
+                            _ => {
+                                return Err(MapParseError::InvalidNumber {
+                                    line: line_number,
+                                    token: token.to_string(),
+                                })
+                            }
+                        };
+                    }
+                    if let Some(token) = fields.get(3) {
+                        wall.texture = parse_token(token, line_number)?;
+                    }
+                    if let Some(token) = fields.get(4) {
+                        needs(3)?;
+                        wall.light = parse_token(token, line_number)?;
+                        finite(&[wall.light], line_number)?;
+                        if !(0.0..=1.0).contains(&wall.light) {
+                            return Err(MapParseError::InvalidNumber {
+                                line: line_number,
+                                token: token.to_string(),
+                            });
+                        }
+                    }
+                    walls.push((line_number, indexs, wall));
+                    progress(walls.len());
+                }
+                // floor and ceiling height followed by the indices of the
+                // walls bounding the sector
+                Section::Sectors => {
+                    let fields = line.split_whitespace().collect::<Vec<&str>>();
+                    if fields.len() < 2 {
+                        return Err(MapParseError::FieldCount {
+                            line: line_number,
+                            min: 2,
+                            max: None,
+                            found: fields.len(),
+                        });
+                    }
+                    let sector = Sector {
+                        floor_height: parse_token(fields[0], line_number)?,
+                        ceiling_height: parse_token(fields[1], line_number)?,
+                        floor_slope: Vector2::zeros(),
+                    };
+                    finite(&[sector.floor_height, sector.ceiling_height], line_number)?;
+                    let indexs = fields[2..]
+                        .iter()
+                        .map(|token| parse_token::<usize>(token, line_number))
+                        .collect::<Result<Vec<usize>, MapParseError>>()?;
+                    sectors.push((line_number, indexs, sector));
+                }
+                // position and kind, optionally followed by the angle the
+                // thing faces in radians
+                Section::Things => {
+                    let fields = line.split_whitespace().collect::<Vec<&str>>();
+                    if fields.len() < 3 || fields.len() > 4 {
+                        return Err(MapParseError::FieldCount {
+                            line: line_number,
+                            min: 3,
+                            max: Some(4),
+                            found: fields.len(),
+                        });
+                    }
+                    let thing = Thing {
+                        pos: Vector2::new(
+                            parse_token(fields[0], line_number)?,
+                            parse_token(fields[1], line_number)?,
+                        ),
+                        kind: parse_token(fields[2], line_number)?,
+                        angle: match fields.get(3) {
+                            Some(token) => parse_token(token, line_number)?,
+                            None => 0.0,
+                        },
+                    };
+                    finite(&[thing.pos.x, thing.pos.y, thing.angle], line_number)?;
+                    out.things.push(thing);
+                }
+                // the index of a sector followed by how much its floor rises
+                // along x and along y
+                Section::Slopes => {
+                    let fields = line.split_whitespace().collect::<Vec<&str>>();
+                    if fields.len() != 3 {
+                        return Err(MapParseError::FieldCount {
+                            line: line_number,
+                            min: 3,
+                            max: Some(3),
+                            found: fields.len(),
+                        });
+                    }
+                    let slope = Vector2::new(
+                        parse_token(fields[1], line_number)?,
+                        parse_token(fields[2], line_number)?,
+                    );
+                    finite(slope.as_slice(), line_number)?;
+                    slopes.push((
+                        line_number,
+                        parse_token::<usize>(fields[0], line_number)?,
+                        slope,
+                    ));
+                }
+            }
+        }
+
+        for (id, (line_number, indexs, wall)) in walls.into_iter().enumerate() {
+            let vertex = |index: usize| {
+                index
+                    .checked_sub(1)
+                    .and_then(|i| verticies.get(i))
+                    .copied()
+                    .ok_or(MapParseError::IndexOutOfRange {
+                        line: line_number,
+                        index,
+                    })
+            };
+            out.walls.push(Wall {
+                two_sided: wall.two_sided,
+                texture: wall.texture,
+                light: wall.light,
+                id: id as u32,
+                ..Wall::with_winding(vertex(indexs[0])?, vertex(indexs[1])?, winding)
+            });
+        }
+        for (line_number, index, slope) in slopes {
+            let (_, _, sector) = index
+                .checked_sub(1)
+                .and_then(|i| sectors.get_mut(i))
+                .ok_or(MapParseError::IndexOutOfRange {
+                    line: line_number,
+                    index,
+                })?;
+            sector.floor_slope = slope;
+        }
+        for (line_number, indexs, sector) in sectors {
+            for index in indexs {
+                let wall = index
+                    .checked_sub(1)
+                    .and_then(|i| out.walls.get_mut(i))
+                    .ok_or(MapParseError::IndexOutOfRange {
+                        line: line_number,
+                        index,
+                    })?;
+                wall.set_sector(out.sectors.len(), &sector);
+            }
+            out.sectors.push(sector);
+        }
+
+        Ok(out)
+    }
+    /// Writes the map in the format read by [`Map::from_reader`], starting
+    /// with a header for [`Map::FORMAT_VERSION`]. Endpoints shared between
+    /// walls are written as a single vertex.
+    pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut verticies = vec![];
+        let mut indexs = HashMap::new();
+        let mut walls = vec![];
+        for wall in &self.walls {
+            let mut index = |point: Vector2<f64>| {
+                *indexs
+                    .entry((point.x.to_bits(), point.y.to_bits()))
+                    .or_insert_with(|| {
+                        verticies.push(point);
+                        verticies.len()
+                    })
+            };
+            walls.push((index(wall.p1), index(wall.p2)));
+        }
+
+        writeln!(w, "version {}", Self::FORMAT_VERSION)?;
+        writeln!(w, "verticies")?;
+        for vertex in &verticies {
+            writeln!(w, "{:?} {:?}", vertex.x, vertex.y)?;
+        }
+        writeln!(w, "walls")?;
+        for (wall, (i1, i2)) in self.walls.iter().zip(&walls) {
+            write!(w, "{} {}", i1, i2)?;
+            // optional fields are positional, so only trailing defaults can
+            // be left out
+            let optional = [
+                (u8::from(wall.two_sided).to_string(), "0"),
+                (wall.texture.to_string(), "0"),
+                (format!("{:?}", wall.light), "1.0"),
+            ];
+            let len = optional
+                .iter()
+                .rposition(|(value, default)| value != default)
+                .map_or(0, |i| i + 1);
+            for (value, _) in &optional[..len] {
+                write!(w, " {}", value)?;
+            }
+            writeln!(w)?;
+        }
+        if !self.sectors.is_empty() {
+            writeln!(w, "sectors")?;
+        }
+        for (i, sector) in self.sectors.iter().enumerate() {
+            write!(w, "{:?} {:?}", sector.floor_height, sector.ceiling_height)?;
+            for (index, wall) in self.walls.iter().enumerate() {
+                if wall.sector == Some(i) {
+                    write!(w, " {}", index + 1)?;
+                }
+            }
+            writeln!(w)?;
+        }
+        if !self.things.is_empty() {
+            writeln!(w, "things")?;
+        }
+        for thing in &self.things {
+            writeln!(
+                w,
+                "{:?} {:?} {} {:?}",
+                thing.pos.x, thing.pos.y, thing.kind, thing.angle
+            )?;
+        }
+        let sloped = self
+            .sectors
+            .iter()
+            .enumerate()
+            .filter(|(_, sector)| sector.floor_slope != Vector2::zeros())
+            .collect::<Vec<_>>();
+        if !sloped.is_empty() {
+            writeln!(w, "slopes")?;
+        }
+        for (i, sector) in sloped {
+            writeln!(
+                w,
+                "{} {:?} {:?}",
+                i + 1,
+                sector.floor_slope.x,
+                sector.floor_slope.y
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{BSPTree, Map, Wall};
+
+/// A [`BSPTree`] laid out as one array of nodes and one of walls, which are
+/// faster to walk than the tree's boxed nodes. Nodes are stored in preorder
+/// and refer to their children by index.
+///
+/// A flat tree can't be edited; flatten the tree again after editing it.
+#[derive(Debug, Clone)]
+pub struct FlatBSP<T = f64> {
+    pub(crate) nodes: Vec<FlatNode>,
+    // each node's segment, coplanar and leaf walls in turn
+    pub(crate) walls: Vec<Wall<T>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FlatNode {
+    // the node's walls in `FlatBSP::walls`, its segment first
+    pub(crate) first: u32,
+    pub(crate) end: u32,
+    // whether the node is a convex leaf, or a leaf left unsorted at the
+    // build's maximum depth
+    pub(crate) convex: bool,
+    pub(crate) unsorted: bool,
+    pub(crate) front: Option<u32>,
+    pub(crate) behind: Option<u32>,
+}
+
+impl<T: RealField + Copy> Map<T> {
+    /// Like [`Map::generate_tree`], flattened into a [`FlatBSP`].
+    pub fn generate_flat_tree(&self) -> Option<FlatBSP<T>> {
+        self.generate_tree().map(|tree| FlatBSP::from(&tree))
+    }
+}
+
+impl<T: RealField + Copy> FlatBSP<T> {
+    /// Every wall of the tree, node by node in preorder.
+    pub fn walls(&self) -> &[Wall<T>] {
+        &self.walls
+    }
+
+    /// Walls ordered back to front as seen from `camera_pos`, in the same
+    /// order as [`BSPTree::get_render_order`] gives for the flattened tree.
+    pub fn render_order(&self, camera_pos: Vector2<T>) -> Vec<Wall<T>> {
+        let mut out = Vec::with_capacity(self.walls.len());
+        self.walk_render_order(camera_pos, |index| out.push(self.walls[index]));
+        out
+    }
+
+    /// Like [`FlatBSP::render_order`], but with each wall given by its index
+    /// in [`FlatBSP::walls`] instead of copied out of it, so each frame only
+    /// copies a `usize` per wall.
+    pub fn render_order_indices(&self, camera_pos: Vector2<T>) -> Vec<usize> {
+        let mut out = Vec::with_capacity(self.walls.len());
+        self.walk_render_order(camera_pos, |index| out.push(index));
+        out
+    }
+
+    // calls `emit` with the index of each wall back to front
+    fn walk_render_order(&self, camera_pos: Vector2<T>, mut emit: impl FnMut(usize)) {
+        let mut stack = vec![Visit::Node(0)];
+        while let Some(visit) = stack.pop() {
+            let node = match visit {
+                Visit::Wall(index) => {
+                    emit(index as usize);
+                    continue;
+                }
+                Visit::Node(index) => self.nodes[index as usize],
+            };
+            let segment = &self.walls[node.first as usize];
+            let (far, near) = if segment.in_front_point(&camera_pos) {
+                (node.behind, node.front)
+            } else {
+                (node.front, node.behind)
+            };
+            // pushed in reverse so they pop as far, node walls, near
+            stack.extend(near.map(Visit::Node));
+            if node.unsorted {
+                // farthest first, so pushed nearest first
+                let mut walls = (node.first..node.end)
+                    .map(|i| (self.walls[i as usize].distance_to_point(camera_pos), i))
+                    .collect::<Vec<_>>();
+                walls.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+                stack.extend(walls.into_iter().rev().map(|(_, i)| Visit::Wall(i)));
+            } else if node.convex {
+                // walls the camera sees from the front are further away in a
+                // convex leaf; each group pops in the order it is stored
+                let walls = (node.first..node.end).rev();
+                let faces = |index: &u32| self.walls[*index as usize].in_front_point(&camera_pos);
+                stack.extend(walls.clone().filter(|i| !faces(i)).map(Visit::Wall));
+                stack.extend(walls.filter(faces).map(Visit::Wall));
+            } else {
+                // walls on one line can't hide one another
+                stack.extend((node.first..node.end).map(Visit::Wall));
+            }
+            stack.extend(far.map(Visit::Node));
+        }
+    }
+}
+
+/// Flattens the tree.
+///
+/// # Panics
+///
+/// If the tree has more than `u32::MAX` nodes or walls.
+impl<T: RealField + Copy> From<&BSPTree<T>> for FlatBSP<T> {
+    fn from(tree: &BSPTree<T>) -> FlatBSP<T> {
+        let index = |i: usize| u32::try_from(i).expect("tree is too large to flatten");
+        let nodes = tree.preorder();
+        let children = BSPTree::preorder_children(&nodes);
+        let mut out = FlatBSP {
+            nodes: Vec::with_capacity(nodes.len()),
+            walls: Vec::with_capacity(tree.wall_count),
+        };
+        for ((_, node), [front, behind]) in nodes.iter().zip(children) {
+            let first = index(out.walls.len());
+            out.walls.extend(node.walls());
+            out.nodes.push(FlatNode {
+                first,
+                end: index(out.walls.len()),
+                convex: !node.leaf.is_empty() && !node.unsorted,
+                unsorted: node.unsorted,
+                front: front.map(index),
+                behind: behind.map(index),
+            });
+        }
+        out
+    }
+}
+
+enum Visit {
+    Node(u32),
+    Wall(u32),
+}
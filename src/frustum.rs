@@ -0,0 +1,90 @@
+use nalgebra::{convert, RealField, Rotation2, Vector2};
+
+use crate::{wall::tolerance, Wall};
+
+// the view cone from a camera, split along the facing direction so each half
+// is convex even for fields of view wider than 180 degrees
+pub(crate) struct Frustum<T> {
+    apex: Vector2<T>,
+    facing: Vector2<T>,
+    cos_half_fov: T,
+    edges: [Vector2<T>; 2],
+    unbounded: bool,
+}
+
+impl<T: RealField + Copy> Frustum<T> {
+    pub(crate) fn new(apex: Vector2<T>, facing: Vector2<T>, fov: T) -> Frustum<T> {
+        let facing = facing.try_normalize(T::zero()).unwrap_or_else(Vector2::x);
+        let half_fov = fov / convert(2.0);
+        Frustum {
+            apex,
+            facing,
+            cos_half_fov: half_fov.cos(),
+            edges: [
+                Rotation2::new(half_fov) * facing,
+                Rotation2::new(-half_fov) * facing,
+            ],
+            unbounded: fov >= T::two_pi(),
+        }
+    }
+
+    pub(crate) fn contains_point(&self, point: Vector2<T>) -> bool {
+        let diff = point - self.apex;
+        self.unbounded || diff.dot(&self.facing) >= diff.norm() * self.cos_half_fov
+    }
+
+    pub(crate) fn intersects(&self, wall: &Wall<T>) -> bool {
+        if self.contains_point(wall.p1) || self.contains_point(wall.p2) {
+            return true;
+        }
+        // with both endpoints outside, the wall is only seen if it crosses an
+        // edge of the cone or passes through the apex
+        let (d1, d2) = (wall.p1 - self.apex, wall.p2 - self.apex);
+        let through_apex = d1.perp(&d2).abs() <= tolerance::<T>(1e-9) * d1.norm() * d2.norm()
+            && d1.dot(&d2) < T::zero();
+        through_apex
+            || self
+                .edges
+                .iter()
+                .any(|edge| wall.ray_intersection(self.apex, *edge).is_some())
+    }
+
+    // whether the cone may reach the box from `min` to `max`, which it can't
+    // if, for each half of the cone, every corner of the box is outside one
+    // edge of that half
+    pub(crate) fn reaches_box(&self, (min, max): (Vector2<T>, Vector2<T>)) -> bool {
+        if self.unbounded {
+            return true;
+        }
+        let corners = [
+            min,
+            Vector2::new(max.x, min.y),
+            max,
+            Vector2::new(min.x, max.y),
+        ]
+        .map(|corner| corner - self.apex);
+        // each half is the wedge counterclockwise from its first edge and
+        // clockwise from its second
+        [(self.facing, self.edges[0]), (self.edges[1], self.facing)]
+            .iter()
+            .any(|(low, high)| {
+                let limit = |d: &Vector2<T>| -tolerance::<T>(1e-9) * d.norm();
+                corners.iter().any(|d| low.perp(d) >= limit(d))
+                    && corners.iter().any(|d| d.perp(high) >= limit(d))
+            })
+    }
+
+    // whether any point of the cone is on the front (or behind) side of
+    // `plane`; a linear function is largest over a convex cone at its apex
+    // unless it grows along one of the cone's edges
+    pub(crate) fn reaches_side(&self, plane: &Wall<T>, front: bool) -> bool {
+        if self.unbounded {
+            return true;
+        }
+        let normal = if front { plane.forward } else { -plane.forward };
+        normal.dot(&(self.apex - plane.p1)) >= T::zero()
+            || [self.edges[0], self.facing, self.edges[1]]
+                .iter()
+                .any(|edge| normal.dot(edge) > T::zero())
+    }
+}
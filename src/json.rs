@@ -0,0 +1,47 @@
+use alloc::string::String;
+use core::fmt::Write;
+
+use nalgebra::Vector2;
+
+use crate::BSPTree;
+
+impl BSPTree {
+    /// The walls of [`BSPTree::get_render_order`] as a JSON array of draw
+    /// commands, such as for a renderer written in JavaScript. Each command
+    /// is an object like
+    /// `{"p1":[0.0,0.0],"p2":[1.0,0.0],"texture":0,"light":1.0}`. Numbers
+    /// that JSON can't represent, such as NaN, are written as `null`.
+    pub fn render_order_json(&self, camera_pos: Vector2<f64>) -> String {
+        let mut out = String::from("[");
+        for (i, wall) in self.get_render_order(camera_pos).iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(
+                out,
+                r#"{{"p1":[{},{}],"p2":[{},{}],"texture":{},"light":{}}}"#,
+                Number(wall.p1.x),
+                Number(wall.p1.y),
+                Number(wall.p2.x),
+                Number(wall.p2.y),
+                wall.texture,
+                Number(wall.light)
+            );
+        }
+        out.push(']');
+        out
+    }
+}
+
+// an `f64` written as a JSON number, which has no infinities or NaN
+struct Number(f64);
+
+impl core::fmt::Display for Number {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.0.is_finite() {
+            write!(f, "{:?}", self.0)
+        } else {
+            f.write_str("null")
+        }
+    }
+}
@@ -0,0 +1,59 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod binary;
+mod bsp;
+mod build;
+mod camera;
+mod collision;
+mod columns;
+mod edit;
+#[cfg(feature = "std")]
+mod file;
+mod flat;
+mod frustum;
+mod json;
+mod loops;
+mod map;
+mod merge;
+mod nearest;
+mod order;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod polygon;
+mod projection;
+#[cfg(feature = "std")]
+mod quake;
+mod random;
+mod rect;
+mod regions;
+mod report;
+mod split;
+mod stats;
+mod svg;
+mod things;
+mod transform;
+mod validate;
+mod visibility;
+mod wall;
+
+pub use bsp::BSPTree;
+pub use build::{BuildOptions, BuildProfiler, SplitHeuristic};
+pub use camera::Camera;
+pub use columns::ColumnBuffer;
+#[cfg(feature = "std")]
+pub use file::MapParseError;
+pub use flat::FlatBSP;
+pub use map::{Map, Sector, Thing};
+pub use order::OrderError;
+pub use projection::ProjectedWall;
+pub use report::SplitReport;
+pub use stats::TreeStats;
+pub use things::ThingIndex;
+pub use validate::MapError;
+pub use visibility::{VisibilityTrace, WallTrace, WallVisibility};
+pub use wall::{Wall, Winding};
+
+pub use nalgebra;
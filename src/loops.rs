@@ -0,0 +1,145 @@
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{map, polygon, wall::tolerance, Map, Wall};
+
+impl<T: RealField + Copy> Map<T> {
+    /// The walls joined end to end into closed loops, for features that need
+    /// the outline of each room or pillar. Each loop is its corners in order,
+    /// the last joining the first, as taken by [`Map::from_polygon`].
+    /// Endpoints closer together than a billionth of the map's size count as
+    /// one point, as in [`Map::find_open_loops`], and each corner is the
+    /// leftmost endpoint of the ones it stands for.
+    ///
+    /// Loops pass each corner once, so two rooms touching at a corner are two
+    /// loops. Which way a loop goes round doesn't depend on the walls'
+    /// facing. Walls that don't close a loop are left out; see
+    /// [`Map::open_chains`].
+    pub fn loops(&self) -> Vec<Vec<Vector2<T>>> {
+        trace(&self.walls).0
+    }
+
+    /// The walls left out of [`Map::loops`] joined end to end, each chain
+    /// its points from one loose end to the other, such as a dangling wall
+    /// that should have closed a room. A map whose walls all close loops has
+    /// none.
+    pub fn open_chains(&self) -> Vec<Vec<Vector2<T>>> {
+        trace(&self.walls).1
+    }
+
+    /// The area enclosed by the walls, such as for scaling a room's enemy
+    /// count by its size. The area inside each loop of [`Map::loops`] is
+    /// added, less the area of each loop inside another, such as a
+    /// pillar in a room; a loop inside a hole adds to the area again.
+    ///
+    /// Walls that don't close a loop add nothing. The loops shouldn't cross
+    /// or touch one another, or holes may be counted as rooms.
+    pub fn enclosed_area(&self) -> T {
+        let loops = self.loops();
+        let mut area = T::zero();
+        for (i, points) in loops.iter().enumerate() {
+            // each loop around this one turns a room into a hole or back
+            let depth = loops
+                .iter()
+                .enumerate()
+                .filter(|(j, other)| *j != i && contains(other, points[0]))
+                .count();
+            let size = polygon::signed_area(points).abs();
+            area += if depth % 2 == 1 { -size } else { size };
+        }
+        area
+    }
+}
+
+// points in order along a loop or chain
+type Path<T> = Vec<Vector2<T>>;
+
+// the closed loops and open chains of walls joined end to end
+fn trace<T: RealField + Copy>(walls: &[Wall<T>]) -> (Vec<Path<T>>, Vec<Path<T>>) {
+    let (min, max) = map::bounds(walls);
+    let epsilon = tolerance::<T>(1e-9) * (max - min).max().max(T::one());
+
+    // each endpoint's point, the leftmost endpoint of its cluster
+    let mut ends = (0..walls.len() * 2).collect::<Vec<_>>();
+    let end = |i: usize| [walls[i / 2].p1, walls[i / 2].p2][i % 2];
+    ends.sort_by(|a, b| end(*a).x.partial_cmp(&end(*b).x).unwrap_or(Ordering::Equal));
+    let mut points = vec![];
+    let mut point_of = vec![usize::MAX; ends.len()];
+    for i in 0..ends.len() {
+        if point_of[ends[i]] != usize::MAX {
+            continue;
+        }
+        let shared = end(ends[i]);
+        for &j in &ends[i..] {
+            if end(j).x - shared.x > epsilon {
+                break;
+            }
+            if point_of[j] == usize::MAX && (end(j) - shared).norm() <= epsilon {
+                point_of[j] = points.len();
+            }
+        }
+        points.push(shared);
+    }
+
+    // the walls at each point, skipping walls whose ends became one point
+    let mut edges = vec![vec![]; points.len()];
+    for wall in 0..walls.len() {
+        let (a, b) = (point_of[2 * wall], point_of[2 * wall + 1]);
+        if a != b {
+            edges[a].push(wall);
+            edges[b].push(wall);
+        }
+    }
+
+    // walks starting at a point with an odd number of walls end at another
+    // such point, so once they are all walked every walk goes round loops
+    let mut used = vec![false; walls.len()];
+    let mut loops = vec![];
+    let mut chains = vec![];
+    let starts = (0..points.len())
+        .filter(|p| edges[*p].len() % 2 == 1)
+        .chain(0..points.len())
+        .collect::<Vec<_>>();
+    for start in starts {
+        while edges[start].iter().any(|wall| !used[*wall]) {
+            let mut path = vec![start];
+            let mut at = start;
+            while let Some(&wall) = edges[at].iter().find(|wall| !used[**wall]) {
+                used[wall] = true;
+                at = if point_of[2 * wall] == at {
+                    point_of[2 * wall + 1]
+                } else {
+                    point_of[2 * wall]
+                };
+                // coming back to a point on the path closes a loop, which is
+                // split off so loops pass each point once
+                if let Some(k) = path.iter().position(|p| *p == at) {
+                    loops.push(path.split_off(k).into_iter().map(|p| points[p]).collect());
+                }
+                path.push(at);
+            }
+            if path.len() > 1 {
+                chains.push(path.into_iter().map(|p| points[p]).collect());
+            }
+        }
+    }
+    (loops, chains)
+}
+
+// whether `point` is inside the closed loop through `polygon`, counting the
+// loop's edges that a ray from it to the right crosses
+fn contains<T: RealField + Copy>(polygon: &[Vector2<T>], point: Vector2<T>) -> bool {
+    let mut inside = false;
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
@@ -0,0 +1,210 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::{RealField, Scalar, Vector2};
+
+use crate::Wall;
+
+/// A set of walls, loaded from a map file or built in code.
+///
+/// The walls are read through [`Map::walls`]; build a [`BSPTree`](crate::BSPTree) with
+/// [`Map::generate_tree`] to query them. Map files are read and written with
+/// `f64` coordinates; [`Map::cast`] converts a map to another scalar type.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T: RealField + Copy + serde::Serialize",
+        deserialize = "T: RealField + Copy + serde::Deserialize<'de>"
+    ))
+)]
+pub struct Map<T = f64> {
+    pub(crate) walls: Vec<Wall<T>>,
+    pub(crate) sectors: Vec<Sector<T>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) things: Vec<Thing<T>>,
+    // modification time of the file the map was last read from, for
+    // `Map::reload_if_changed`
+    #[cfg(feature = "std")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) modified: Option<std::time::SystemTime>,
+}
+
+/// Maps are equal if their walls are equal in order, as compared by
+/// [`Wall`]'s `PartialEq`, and their sectors and things are equal, wherever
+/// they were read from.
+impl<T: Scalar> PartialEq for Map<T> {
+    fn eq(&self, other: &Map<T>) -> bool {
+        self.walls == other.walls && self.sectors == other.sectors && self.things == other.things
+    }
+}
+
+/// Floor and ceiling heights shared by the walls bounding a region. Walls
+/// refer to their sector by its index in [`Map::sectors`].
+///
+/// The floor is the plane `z = floor_height + floor_slope.x * x +
+/// floor_slope.y * y`, so `floor_height` is its height at the origin, and a
+/// zero slope gives a flat floor such as a room's. A nonzero slope gives a
+/// ramp. The ceiling is always flat.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T: RealField + Copy + serde::Serialize",
+        deserialize = "T: RealField + Copy + serde::Deserialize<'de>"
+    ))
+)]
+pub struct Sector<T = f64> {
+    pub floor_height: T,
+    pub ceiling_height: T,
+    /// How much the floor rises per unit along x and along y.
+    #[cfg_attr(feature = "serde", serde(default = "Vector2::zeros"))]
+    pub floor_slope: Vector2<T>,
+}
+
+impl<T: Scalar> PartialEq for Sector<T> {
+    fn eq(&self, other: &Sector<T>) -> bool {
+        self.floor_height == other.floor_height
+            && self.ceiling_height == other.ceiling_height
+            && self.floor_slope == other.floor_slope
+    }
+}
+
+impl<T: RealField + Copy> Sector<T> {
+    /// Height of the floor at `point`.
+    pub fn floor_height_at(&self, point: Vector2<T>) -> T {
+        self.floor_height + self.floor_slope.dot(&point)
+    }
+}
+
+/// Something placed in the map that isn't part of its geometry, such as the
+/// player start, a monster or an item. Things travel with the map but are
+/// not stored in its BSP tree.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(bound(
+        serialize = "T: RealField + Copy + serde::Serialize",
+        deserialize = "T: RealField + Copy + serde::Deserialize<'de>"
+    ))
+)]
+pub struct Thing<T = f64> {
+    pub pos: Vector2<T>,
+    /// What the thing is, as a number the game gives meaning to.
+    pub kind: u32,
+    /// Direction the thing faces, in radians counterclockwise from the x
+    /// axis.
+    pub angle: T,
+}
+
+impl<T: Scalar> PartialEq for Thing<T> {
+    fn eq(&self, other: &Thing<T>) -> bool {
+        self.pos == other.pos && self.kind == other.kind && self.angle == other.angle
+    }
+}
+
+pub(crate) fn bounds<'a, T: RealField + Copy>(
+    walls: impl IntoIterator<Item = &'a Wall<T>>,
+) -> (Vector2<T>, Vector2<T>) {
+    let mut points = walls.into_iter().flat_map(|wall| [wall.p1, wall.p2]);
+    let Some(first) = points.next() else {
+        return (Vector2::zeros(), Vector2::zeros());
+    };
+    points.fold((first, first), |(min, max), point| {
+        (min.inf(&point), max.sup(&point))
+    })
+}
+
+impl<T: RealField + Copy> Map<T> {
+    /// A map of `walls`, with their [ids](Wall::id) numbered from 0 in order.
+    pub fn from_walls(mut walls: Vec<Wall<T>>) -> Map<T> {
+        for (id, wall) in walls.iter_mut().enumerate() {
+            wall.id = id as u32;
+        }
+        Map {
+            walls,
+            sectors: vec![],
+            things: vec![],
+            #[cfg(feature = "std")]
+            modified: None,
+        }
+    }
+
+    /// Adds a wall from `p1` to `p2`, with the id after the largest in the
+    /// map.
+    pub fn push_wall(&mut self, p1: Vector2<T>, p2: Vector2<T>) {
+        let id = self.next_id();
+        self.walls.push(Wall {
+            id,
+            ..Wall::new(p1, p2)
+        });
+    }
+
+    // the id after the largest of the map's walls
+    pub(crate) fn next_id(&self) -> u32 {
+        self.walls.iter().map(|wall| wall.id + 1).max().unwrap_or(0)
+    }
+
+    pub fn walls(&self) -> &[Wall<T>] {
+        &self.walls
+    }
+
+    pub fn sectors(&self) -> &[Sector<T>] {
+        &self.sectors
+    }
+
+    pub fn things(&self) -> &[Thing<T>] {
+        &self.things
+    }
+
+    pub fn push_thing(&mut self, thing: Thing<T>) {
+        self.things.push(thing);
+    }
+
+    /// Minimum and maximum corners of the axis-aligned box around every wall
+    /// endpoint, or two zero corners for a map without walls.
+    pub fn bounds(&self) -> (Vector2<T>, Vector2<T>) {
+        bounds(&self.walls)
+    }
+
+    pub fn total_wall_length(&self) -> T {
+        self.walls
+            .iter()
+            .map(Wall::length)
+            .fold(T::zero(), |a, b| a + b)
+    }
+
+    /// The map with its coordinates and heights converted to `U`, for
+    /// example to `f32` after reading it from a file.
+    pub fn cast<U: RealField + Copy>(&self) -> Map<U> {
+        let convert = |x: T| nalgebra::convert::<_, U>(x.to_subset().unwrap_or(f64::NAN));
+        Map {
+            walls: self.walls.iter().map(Wall::cast).collect(),
+            sectors: self
+                .sectors
+                .iter()
+                .map(|sector| Sector {
+                    floor_height: convert(sector.floor_height),
+                    ceiling_height: convert(sector.ceiling_height),
+                    floor_slope: Vector2::new(
+                        convert(sector.floor_slope.x),
+                        convert(sector.floor_slope.y),
+                    ),
+                })
+                .collect(),
+            things: self
+                .things
+                .iter()
+                .map(|thing| Thing {
+                    pos: Vector2::new(convert(thing.pos.x), convert(thing.pos.y)),
+                    kind: thing.kind,
+                    angle: convert(thing.angle),
+                })
+                .collect(),
+            #[cfg(feature = "std")]
+            modified: self.modified,
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{wall::tolerance, Map, Sector, Thing, Wall};
+
+impl<T: RealField + Copy> Map<T> {
+    /// Joins walls in `walls` that continue one another, such as the pieces a
+    /// BSP split cut a wall into, so there are fewer walls to draw.
+    ///
+    /// Two walls are joined when one starts where the other ends, both point
+    /// the same way along the same line, and they share texture, light,
+    /// sector and two-sidedness, with the texture continuing across the join. The pieces
+    /// may come in any order; each joined wall takes the place of the
+    /// earliest of its pieces.
+    pub fn merge_collinear(walls: &[Wall<T>]) -> Vec<Wall<T>> {
+        let starts = sorted_by(walls, |wall| wall.p1);
+        let ends = sorted_by(walls, |wall| wall.p2);
+        let mut used = vec![false; walls.len()];
+
+        let mut out = vec![];
+        for i in 0..walls.len() {
+            if used[i] {
+                continue;
+            }
+            used[i] = true;
+            let mut first = i;
+            while let Some(previous) = find(&ends, walls[first].p1, |j| {
+                !used[j] && continues(&walls[j], &walls[first])
+            }) {
+                used[previous] = true;
+                first = previous;
+            }
+            let mut last = i;
+            while let Some(next) = find(&starts, walls[last].p2, |j| {
+                !used[j] && continues(&walls[last], &walls[j])
+            }) {
+                used[next] = true;
+                last = next;
+            }
+            out.push(walls[first].with_ends(walls[first].p1, walls[last].p2));
+        }
+        out
+    }
+
+    /// Appends the walls, sectors and things of `other` moved by `offset`,
+    /// such as to join the tiles of a large level into one map for a single
+    /// tree. The [ids](Wall::id) of the appended walls are moved past the
+    /// largest in this map, so walls sharing an id in `other` still share one.
+    pub fn merge(&mut self, other: &Map<T>, offset: Vector2<T>) {
+        self.append(other, offset, false);
+    }
+
+    /// Like [`Map::merge`], but leaves out the walls of `other` that join
+    /// exactly the same two points as a wall in this map before the merge,
+    /// either way round, like the shared edge of two neighbouring tiles. The
+    /// wall already in the map is kept.
+    pub fn merge_deduplicated(&mut self, other: &Map<T>, offset: Vector2<T>) {
+        self.append(other, offset, true);
+    }
+
+    /// Replaces each pair of walls that join the same two points in opposite
+    /// directions and face away from one another, such as the shared border
+    /// of two rooms drawn once for each room, with a single
+    /// [two-sided](Wall::two_sided) wall, so the border is drawn once and
+    /// becomes a portal between the rooms. Walls facing the same side, like
+    /// one drawn each way round with different [windings](crate::Winding), are left
+    /// alone. The earlier wall of a pair is kept, with its texture and
+    /// sector, and the later one is removed. Points must match exactly, as in
+    /// [`Map::merge_deduplicated`].
+    pub fn dedup_coincident(&mut self) {
+        let starts = sorted_by(&self.walls, |wall| wall.p1);
+        let mut removed = vec![false; self.walls.len()];
+        for i in 0..self.walls.len() {
+            if removed[i] {
+                continue;
+            }
+            let (p1, p2) = (self.walls[i].p1, self.walls[i].p2);
+            let forward = self.walls[i].forward;
+            if let Some(j) = find(&starts, p2, |j| {
+                j > i
+                    && !removed[j]
+                    && self.walls[j].p2 == p1
+                    && self.walls[j].forward.dot(&forward) < T::zero()
+            }) {
+                removed[j] = true;
+                self.walls[i].two_sided = true;
+            }
+        }
+        let mut removed = removed.into_iter();
+        self.walls.retain(|_| !removed.next().unwrap_or(false));
+    }
+
+    /// Moves wall endpoints within `tolerance` of one another to one shared
+    /// position, such as the corners of imported walls that should meet but
+    /// are a rounding error apart, so later passes like
+    /// [`Map::dedup_coincident`] and [`Map::find_open_loops`] see them meet.
+    /// Endpoints are taken from left to right, and each one not yet moved
+    /// pulls in the others within `tolerance` of it. Walls left with both
+    /// ends at one position are removed.
+    pub fn weld_vertices(&mut self, tolerance: T) {
+        let mut ends = self
+            .walls
+            .iter()
+            .enumerate()
+            .flat_map(|(i, wall)| [(wall.p1, i, false), (wall.p2, i, true)])
+            .collect::<Vec<_>>();
+        ends.sort_by(|a, b| a.0.x.partial_cmp(&b.0.x).unwrap_or(Ordering::Equal));
+
+        let mut moved = vec![false; ends.len()];
+        for i in 0..ends.len() {
+            if moved[i] {
+                continue;
+            }
+            let shared = ends[i].0;
+            for j in i..ends.len() {
+                let (point, wall, second) = ends[j];
+                if point.x - shared.x > tolerance {
+                    break;
+                }
+                if moved[j] || (point - shared).norm() > tolerance {
+                    continue;
+                }
+                moved[j] = true;
+                let wall = &mut self.walls[wall];
+                let (p1, p2) = if second {
+                    (wall.p1, shared)
+                } else {
+                    (shared, wall.p2)
+                };
+                *wall = wall.with_ends(p1, p2);
+            }
+        }
+        self.walls.retain(|wall| wall.p1 != wall.p2);
+    }
+
+    fn append(&mut self, other: &Map<T>, offset: Vector2<T>, deduplicate: bool) {
+        let starts = if deduplicate {
+            sorted_by(&self.walls, |wall| wall.p1)
+        } else {
+            vec![]
+        };
+        let sectors = self.sectors.len();
+        let ids = self.next_id();
+        for wall in &other.walls {
+            let (p1, p2) = (wall.p1 + offset, wall.p2 + offset);
+            let duplicate = deduplicate
+                && (find(&starts, p1, |j| self.walls[j].p2 == p2).is_some()
+                    || find(&starts, p2, |j| self.walls[j].p2 == p1).is_some());
+            if !duplicate {
+                self.walls.push(Wall {
+                    p1,
+                    p2,
+                    floor_height: wall.floor_height - wall.floor_slope.dot(&offset),
+                    sector: wall.sector.map(|sector| sector + sectors),
+                    id: wall.id + ids,
+                    ..*wall
+                });
+            }
+        }
+        self.sectors
+            .extend(other.sectors.iter().map(|sector| Sector {
+                floor_height: sector.floor_height - sector.floor_slope.dot(&offset),
+                ..*sector
+            }));
+        self.things.extend(other.things.iter().map(|thing| Thing {
+            pos: thing.pos + offset,
+            ..*thing
+        }));
+    }
+}
+
+// whether `next` carries on from the end of `wall` as one straight wall
+fn continues<T: RealField + Copy>(wall: &Wall<T>, next: &Wall<T>) -> bool {
+    let (a, b) = (wall.p2 - wall.p1, next.p2 - next.p1);
+    let scale = wall.length() * next.length();
+    wall.p2 == next.p1
+        && a.perp(&b).abs() <= tolerance::<T>(1e-9) * scale
+        && a.dot(&b) > T::zero()
+        && wall.texture == next.texture
+        && wall.light == next.light
+        && wall.sector == next.sector
+        && wall.two_sided == next.two_sided
+        && (wall.u_offset + wall.length() - next.u_offset).abs()
+            <= tolerance::<T>(1e-9) * (T::one() + next.u_offset.abs())
+}
+
+fn compare<T: RealField + Copy>(a: Vector2<T>, b: Vector2<T>) -> Ordering {
+    a.x.partial_cmp(&b.x)
+        .unwrap_or(Ordering::Equal)
+        .then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+}
+
+// indices of `walls` sorted by the point `key` picks from each wall
+fn sorted_by<T: RealField + Copy>(
+    walls: &[Wall<T>],
+    key: fn(&Wall<T>) -> Vector2<T>,
+) -> Vec<(Vector2<T>, usize)> {
+    let mut out = walls
+        .iter()
+        .enumerate()
+        .map(|(i, wall)| (key(wall), i))
+        .collect::<Vec<_>>();
+    out.sort_by(|a, b| compare(a.0, b.0));
+    out
+}
+
+// the first wall keyed at `point` that `accept` takes
+fn find<T: RealField + Copy>(
+    sorted: &[(Vector2<T>, usize)],
+    point: Vector2<T>,
+    accept: impl Fn(usize) -> bool,
+) -> Option<usize> {
+    let start = sorted.partition_point(|(key, _)| compare(*key, point) == Ordering::Less);
+    sorted[start..]
+        .iter()
+        .take_while(|(key, _)| *key == point)
+        .map(|(_, i)| *i)
+        .find(|&i| accept(i))
+}
@@ -0,0 +1,58 @@
+use alloc::vec;
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{BSPTree, Map, Wall};
+
+impl<T: RealField + Copy> Map<T> {
+    /// The wall closest to `point` and its distance, checking every wall. A
+    /// [`BSPTree`] answers the same query faster with
+    /// [`BSPTree::nearest_wall`].
+    pub fn nearest_wall(&self, point: Vector2<T>) -> Option<(T, &Wall<T>)> {
+        self.walls
+            .iter()
+            .map(|wall| (wall.distance_to_point(point), wall))
+            .fold(None, |best, candidate| match best {
+                Some((distance, _)) if distance <= candidate.0 => best,
+                _ => Some(candidate),
+            })
+    }
+}
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// The wall in the tree closest to `point` and its distance.
+    ///
+    /// The side of each split containing `point` is searched first, and the
+    /// other side is skipped when the split's plane is already further away
+    /// than the nearest wall found.
+    pub fn nearest_wall(&self, point: Vector2<T>) -> Option<(T, &Wall<T>)> {
+        let mut best: Option<(T, &Wall<T>)> = None;
+        // each node with a lower bound on the distance to its walls
+        let mut stack = vec![(self, T::zero())];
+        while let Some((node, bound)) = stack.pop() {
+            if best.is_some_and(|(distance, _)| distance <= bound) {
+                continue;
+            }
+            for wall in node.walls() {
+                let distance = wall.distance_to_point(point);
+                if best.is_none_or(|(best, _)| distance < best) {
+                    best = Some((distance, wall));
+                }
+            }
+            let plane = &node.segment;
+            let offset = plane.signed_distance(point);
+            let (near, far) = if offset > T::zero() {
+                (&node.front, &node.behind)
+            } else {
+                (&node.behind, &node.front)
+            };
+            if let Some(far) = far.as_ref() {
+                stack.push((far, bound.max(offset.abs())));
+            }
+            if let Some(near) = near.as_ref() {
+                stack.push((near, bound));
+            }
+        }
+        best
+    }
+}
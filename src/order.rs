@@ -0,0 +1,105 @@
+use core::fmt;
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{wall::tolerance, BSPTree, Wall};
+
+/// A pair of walls drawn in the wrong order, found by
+/// [`BSPTree::verify_order`]. Walls are identified by their index in the
+/// checked render order, and `occluder` hides part of `occluded` from the
+/// camera but comes before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderError {
+    pub occluder: usize,
+    pub occluded: usize,
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "wall {} is drawn before wall {}, which it hides",
+            self.occluder, self.occluded
+        )
+    }
+}
+
+impl core::error::Error for OrderError {}
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// Checks that [`BSPTree::get_render_order`] from `camera_pos` draws every
+    /// wall before the walls that hide any part of it, as a painter's
+    /// algorithm needs. Every pair of walls is compared, so this is meant
+    /// for debugging rather than for every frame.
+    pub fn verify_order(&self, camera_pos: Vector2<T>) -> Result<(), OrderError> {
+        Self::check_render_order(&self.get_render_order(camera_pos), camera_pos)
+    }
+
+    /// Like [`BSPTree::verify_order`], for any back to front order of walls
+    /// that don't cross each other, and reports the first pair found in the
+    /// wrong order.
+    pub fn check_render_order(walls: &[Wall<T>], camera_pos: Vector2<T>) -> Result<(), OrderError> {
+        for (i, first) in walls.iter().enumerate() {
+            for (j, second) in walls.iter().enumerate().skip(i + 1) {
+                if hides(first, second, camera_pos) {
+                    return Err(OrderError {
+                        occluder: i,
+                        occluded: j,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// whether `near` hides part of `far` from `eye`, found along the middle of
+// the directions in which the eye sees both
+fn hides<T: RealField + Copy>(near: &Wall<T>, far: &Wall<T>, eye: Vector2<T>) -> bool {
+    let (Some(a), Some(b)) = (view_span(near, eye), view_span(far, eye)) else {
+        return false;
+    };
+    // a span is less than half a turn, so a direction is inside it when it
+    // is counterclockwise from the start and clockwise from the end
+    let inside = |(start, end): (Vector2<T>, Vector2<T>), d: Vector2<T>| {
+        start.perp(&d) >= T::zero() && d.perp(&end) >= T::zero()
+    };
+    let start = if inside(b, a.0) { a.0 } else { b.0 };
+    let end = if inside(b, a.1) { a.1 } else { b.1 };
+    if !inside(a, start) || !inside(b, start) || !inside(a, end) || !inside(b, end) {
+        return false;
+    }
+    // walls that only share a line of sight, such as at a common endpoint,
+    // hide nothing of each other
+    if start.perp(&end) <= tolerance::<T>(1e-9) {
+        return false;
+    }
+    let middle = start + end;
+    match (
+        near.ray_intersection(eye, middle),
+        far.ray_intersection(eye, middle),
+    ) {
+        (Some(near), Some(far)) => near < far * (T::one() - tolerance::<T>(1e-9)),
+        _ => false,
+    }
+}
+
+// unit directions from `eye` to the ends of `wall`, counterclockwise, or
+// `None` if the eye is on the wall's line and sees it edge on
+fn view_span<T: RealField + Copy>(
+    wall: &Wall<T>,
+    eye: Vector2<T>,
+) -> Option<(Vector2<T>, Vector2<T>)> {
+    let (to_p1, to_p2) = (
+        (wall.p1 - eye).try_normalize(T::zero())?,
+        (wall.p2 - eye).try_normalize(T::zero())?,
+    );
+    let turn = to_p1.perp(&to_p2);
+    if turn.abs() <= tolerance::<T>(1e-9) {
+        None
+    } else if turn > T::zero() {
+        Some((to_p1, to_p2))
+    } else {
+        Some((to_p2, to_p1))
+    }
+}
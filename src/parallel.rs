@@ -0,0 +1,74 @@
+use nalgebra::RealField;
+
+use crate::{
+    build::{is_convex, place_walls, tree_create, Place},
+    BSPTree, BuildOptions, Map, Wall,
+};
+
+// subtrees with fewer walls on either side of their split are built on one
+// thread, where splitting the work further costs more than it saves
+const PARALLEL_THRESHOLD: usize = 1024;
+
+impl<T: RealField + Copy> Map<T> {
+    /// Like [`Map::generate_tree`], but builds the front and behind subtrees
+    /// of large nodes on separate threads with rayon. The tree is the same
+    /// as [`Map::generate_tree`] builds. Only nodes with many walls on both
+    /// sides are shared out, so maps whose splits are lopsided build at
+    /// about the sequential speed.
+    pub fn generate_tree_parallel(&self) -> Option<BSPTree<T>> {
+        self.generate_tree_parallel_with_options(BuildOptions::default())
+    }
+
+    /// Like [`Map::generate_tree_with_options`], built on separate threads as
+    /// in [`Map::generate_tree_parallel`]. The tree is the same as
+    /// [`Map::generate_tree_with_options`] builds with `options`.
+    pub fn generate_tree_parallel_with_options(&self, options: BuildOptions) -> Option<BSPTree<T>> {
+        let walls = self
+            .walls
+            .iter()
+            .filter(|wall| !wall.is_degenerate())
+            .copied()
+            .collect::<Vec<Wall<T>>>();
+        build(walls, options)
+    }
+}
+
+// makes the same split choices as `tree_create`, which builds the subtrees
+// too small to be worth sharing out, and the leaves
+fn build<T: RealField + Copy>(walls: Vec<Wall<T>>, options: BuildOptions) -> Option<BSPTree<T>> {
+    let leaf = options.max_depth == Some(0)
+        || (options.convex_leaves && is_convex(&walls, options.tolerance()));
+    if walls.len() <= 2 * PARALLEL_THRESHOLD || leaf {
+        return tree_create(&walls, options);
+    }
+    let split = options.heuristic.choose(&walls, options.tolerance());
+    let (mut coplanar, mut front, mut behind) = (vec![], vec![], vec![]);
+    let splits = place_walls(
+        &walls,
+        split,
+        options.tolerance(),
+        |_, wall, place| match place {
+            Place::Coplanar => coplanar.push(wall),
+            Place::Front => front.push(wall),
+            Place::Behind => behind.push(wall),
+        },
+    );
+    if front.len() < PARALLEL_THRESHOLD || behind.len() < PARALLEL_THRESHOLD {
+        return tree_create(&walls, options);
+    }
+    // the children are a level further down
+    let options = BuildOptions {
+        max_depth: options.max_depth.map(|depth| depth - 1),
+        ..options
+    };
+    let (front, behind) = rayon::join(|| build(front, options), || build(behind, options));
+    Some(BSPTree::node(
+        walls[split],
+        coplanar,
+        vec![],
+        false,
+        splits,
+        front,
+        behind,
+    ))
+}
@@ -0,0 +1,113 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{map, BSPTree, Map, Wall, Winding};
+
+impl<T: RealField + Copy> Map<T> {
+    /// A room bounded by the closed loop through `points`, with a wall from
+    /// each point to the next and one from the last back to the first. The
+    /// walls face into the room whichever way the points go round it. Fewer
+    /// than two points give no walls.
+    pub fn from_polygon(points: &[Vector2<T>]) -> Map<T> {
+        Map::from_walls(loop_walls(points, true))
+    }
+
+    /// Like [`Map::from_polygon`] for the loop `outer`, with a hole cut out of
+    /// the room by each loop of `holes`, such as a pillar. A hole's walls
+    /// face out of it, into the room, whichever way its points go round it.
+    /// The holes should lie inside `outer` without touching one another.
+    pub fn from_polygons(outer: &[Vector2<T>], holes: &[Vec<Vector2<T>>]) -> Map<T> {
+        let mut walls = loop_walls(outer, true);
+        for hole in holes {
+            walls.extend(loop_walls(hole, false));
+        }
+        Map::from_walls(walls)
+    }
+}
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// The corners, counterclockwise, of the convex region of the tree that
+    /// holds `point`: the subsector of the leaf that [`BSPTree::locate`]
+    /// ends at. The region is cut from the bounding box of the tree's walls
+    /// by the line of each segment on the way there, keeping the side of
+    /// `point`, so regions reaching past the outermost walls stop at the box.
+    ///
+    /// A point outside the box gets the part of its region inside the box,
+    /// which doesn't hold the point and may have no corners at all.
+    pub fn leaf_polygon(&self, point: Vector2<T>) -> Vec<Vector2<T>> {
+        let (min, max) = map::bounds(self.iter());
+        let mut polygon = vec![
+            min,
+            Vector2::new(max.x, min.y),
+            max,
+            Vector2::new(min.x, max.y),
+        ];
+        for segment in self.locate(point) {
+            let normal = segment.normal();
+            let offset = normal.dot(&segment.p1);
+            polygon = if segment.in_front_point(&point) {
+                clip(&polygon, -normal, -offset)
+            } else {
+                clip(&polygon, normal, offset)
+            };
+        }
+        dedup(&mut polygon, (max - min).amax() * nalgebra::convert(1e-12));
+        polygon
+    }
+}
+
+// the part of a convex polygon where `normal · p <= offset`
+pub(crate) fn clip<T: RealField + Copy>(
+    polygon: &[Vector2<T>],
+    normal: Vector2<T>,
+    offset: T,
+) -> Vec<Vector2<T>> {
+    let mut out = vec![];
+    for (i, &a) in polygon.iter().enumerate() {
+        let b = polygon[(i + 1) % polygon.len()];
+        let (da, db) = (normal.dot(&a) - offset, normal.dot(&b) - offset);
+        if da <= T::zero() {
+            out.push(a);
+        }
+        if (da < T::zero() && db > T::zero()) || (da > T::zero() && db < T::zero()) {
+            out.push(a + (b - a) * (da / (da - db)));
+        }
+    }
+    out
+}
+
+// drops each corner closer than `tolerance` to the one before it, going
+// round the polygon
+pub(crate) fn dedup<T: RealField + Copy>(polygon: &mut Vec<Vector2<T>>, tolerance: T) {
+    polygon.dedup_by(|a, b| (*a - *b).norm() <= tolerance);
+    if polygon.len() > 1 && (polygon[0] - polygon[polygon.len() - 1]).norm() <= tolerance {
+        polygon.pop();
+    }
+}
+
+// walls round the closed loop through `points`, all facing into it if
+// `inward`, and all out of it otherwise
+pub(crate) fn loop_walls<T: RealField + Copy>(points: &[Vector2<T>], inward: bool) -> Vec<Wall<T>> {
+    if points.len() < 2 {
+        return vec![];
+    }
+    // the front of `Wall::new` is on the left, inside a counterclockwise loop
+    let winding = if (signed_area(points) >= T::zero()) == inward {
+        Winding::CounterClockwise
+    } else {
+        Winding::Clockwise
+    };
+    (0..points.len())
+        .map(|i| Wall::with_winding(points[i], points[(i + 1) % points.len()], winding))
+        .collect()
+}
+
+// the area inside the closed loop through `points`, positive if they go
+// counterclockwise
+pub(crate) fn signed_area<T: RealField + Copy>(points: &[Vector2<T>]) -> T {
+    let twice = (0..points.len())
+        .map(|i| points[i].perp(&points[(i + 1) % points.len()]))
+        .fold(T::zero(), |a, b| a + b);
+    twice / nalgebra::convert(2.0)
+}
@@ -0,0 +1,81 @@
+use nalgebra::{convert, RealField, Vector2};
+
+use crate::{Camera, Wall};
+
+/// Screen columns, view depths and floor heights of a wall's endpoints, left
+/// to right.
+///
+/// Depths are distances along the camera's view direction, which is what
+/// perspective-correct interpolation across the wall needs. Floor heights
+/// differ between the ends of a wall on a [sloped floor](crate::Sector::floor_slope).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProjectedWall<T = f64> {
+    pub left_x: T,
+    pub right_x: T,
+    pub left_depth: T,
+    pub right_depth: T,
+    pub left_floor: T,
+    pub right_floor: T,
+}
+
+impl<T: RealField + Copy> Wall<T> {
+    /// The part of the wall at least `near` in front of `camera`, or `None` if
+    /// all of it is closer or behind.
+    pub fn clip_near(&self, camera: &Camera<T>, near: T) -> Option<Wall<T>> {
+        // the near plane as a wall whose front faces away from the camera
+        let center = camera.position + camera.direction() * near;
+        let plane = Wall::new(center - camera.right(), center + camera.right());
+
+        if let Some(point) = self.intersection(&plane) {
+            let (first, second) = self.splice(point);
+            if plane.in_front_point(&self.p1) {
+                Some(first)
+            } else {
+                Some(second)
+            }
+        } else if plane.in_front(self) {
+            Some(*self)
+        } else {
+            None
+        }
+    }
+
+    /// Projects the wall onto a screen `screen_width` columns wide. An
+    /// endpoint behind the camera's near plane is moved onto it, and walls
+    /// entirely behind it give `None`.
+    pub fn project(&self, camera: &Camera<T>, screen_width: u32) -> Option<ProjectedWall<T>> {
+        let forward = camera.direction();
+        let right = camera.right();
+        let depth = |point: Vector2<T>| (point - camera.position).dot(&forward);
+        let Wall { p1, p2, .. } = self.clip_near(camera, camera.near)?;
+
+        let half_width: T = convert(f64::from(screen_width) / 2.0);
+        let focal = half_width / (camera.fov / convert(2.0)).tan();
+        let column = |point: Vector2<T>| {
+            let diff = point - camera.position;
+            half_width + diff.dot(&right) / diff.dot(&forward) * focal
+        };
+        let (x1, x2) = (column(p1), column(p2));
+        let (z1, z2) = (depth(p1), depth(p2));
+        let (f1, f2) = (self.floor_height_at(p1), self.floor_height_at(p2));
+        Some(if x1 <= x2 {
+            ProjectedWall {
+                left_x: x1,
+                right_x: x2,
+                left_depth: z1,
+                right_depth: z2,
+                left_floor: f1,
+                right_floor: f2,
+            }
+        } else {
+            ProjectedWall {
+                left_x: x2,
+                right_x: x1,
+                left_depth: z2,
+                right_depth: z1,
+                left_floor: f2,
+                right_floor: f1,
+            }
+        })
+    }
+}
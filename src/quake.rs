@@ -0,0 +1,159 @@
+use std::io::BufRead;
+
+use nalgebra::{Vector2, Vector3};
+
+use crate::{
+    file::{finite, parse_token},
+    polygon, Map, MapParseError, Wall,
+};
+
+// a brush face, keeping the points `normal · p <= distance`
+struct Plane {
+    normal: Vector3<f64>,
+    distance: f64,
+    // largest coordinate of the points the face was given by
+    reach: f64,
+}
+
+impl Map {
+    /// Reads the walls where the horizontal plane at height `z` cuts the
+    /// brushes of a Quake `.map` file, such as one saved by TrenchBroom. Each
+    /// cut brush is a convex polygon whose edges become walls facing out of
+    /// it, into the open space around the brushes. Lines starting with `//`
+    /// are comments.
+    ///
+    /// Only the brushes of the `worldspawn` entity are read; other entities,
+    /// including brush entities like doors, are skipped, and so are texture
+    /// names, offsets and sectors. Faces shared by two touching brushes become
+    /// a pair of back-to-back walls. Brushes are assumed to lie within twice
+    /// the largest coordinate of the points that give their faces, which holds
+    /// for maps from any common editor. A `z` on the top or bottom face of a
+    /// brush cuts it too, so pick one between floors and ceilings, such as eye
+    /// height. Patches and other non-brush primitives are rejected.
+    pub fn from_quake_map<R: BufRead>(reader: R, z: f64) -> Result<Map, MapParseError> {
+        let mut walls = vec![];
+        // brushes of the entity being read, kept until its classname is known
+        let mut brushes: Vec<Vec<Plane>> = vec![];
+        let mut worldspawn = false;
+        let mut depth = 0;
+        for (line_number, line) in reader.lines().enumerate() {
+            let line_number = line_number + 1;
+            let line = line.map_err(|error| MapParseError::Read {
+                line: line_number,
+                error,
+            })?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            match (depth, line) {
+                (0, "{") => depth = 1,
+                (1, "{") => {
+                    depth = 2;
+                    brushes.push(vec![]);
+                }
+                (1, "}") => {
+                    if worldspawn {
+                        walls.extend(brushes.iter().flat_map(|brush| slice(brush, z)));
+                    }
+                    brushes.clear();
+                    worldspawn = false;
+                    depth = 0;
+                }
+                (2, "}") => depth = 1,
+                // a `"key" "value"` pair of the entity
+                (1, _) if line.starts_with('"') => {
+                    let parts = line.split('"').collect::<Vec<&str>>();
+                    if parts.get(1) == Some(&"classname") && parts.get(3) == Some(&"worldspawn") {
+                        worldspawn = true;
+                    }
+                }
+                (2, _) if line.starts_with('(') => {
+                    let brush = brushes.last_mut().expect("a brush is open at depth 2");
+                    brush.extend(parse_plane(line, line_number)?);
+                }
+                _ => {
+                    return Err(MapParseError::Unexpected {
+                        line: line_number,
+                        token: line.split_whitespace().next().unwrap_or(line).to_string(),
+                    })
+                }
+            }
+        }
+        if depth != 0 {
+            return Err(MapParseError::UnexpectedEnd);
+        }
+
+        Ok(Map::from_walls(walls))
+    }
+}
+
+// a face line, three points in parentheses followed by a texture name and its
+// alignment; `None` if the points are on one line and give no plane
+fn parse_plane(line: &str, line_number: usize) -> Result<Option<Plane>, MapParseError> {
+    let tokens = line.split_whitespace().collect::<Vec<&str>>();
+    if tokens.len() < 15 {
+        return Err(MapParseError::FieldCount {
+            line: line_number,
+            min: 15,
+            max: None,
+            found: tokens.len(),
+        });
+    }
+    let mut points = [Vector3::zeros(); 3];
+    for (point, tokens) in points.iter_mut().zip(tokens.chunks(5)) {
+        for (token, expected) in [(tokens[0], "("), (tokens[4], ")")] {
+            if token != expected {
+                return Err(MapParseError::Unexpected {
+                    line: line_number,
+                    token: token.to_string(),
+                });
+            }
+        }
+        for (coordinate, token) in point.iter_mut().zip(&tokens[1..4]) {
+            *coordinate = parse_token(token, line_number)?;
+        }
+        finite(point.as_slice(), line_number)?;
+    }
+
+    // the points go clockwise seen from outside the brush
+    let normal = (points[0] - points[1]).cross(&(points[2] - points[1]));
+    Ok(normal.try_normalize(0.0).map(|normal| Plane {
+        normal,
+        distance: normal.dot(&points[1]),
+        reach: points.iter().map(|point| point.amax()).fold(0.0, f64::max),
+    }))
+}
+
+// the walls around the cut through a brush at height `z`, facing out of it
+fn slice(planes: &[Plane], z: f64) -> Vec<Wall> {
+    let reach = 2.0 * planes.iter().map(|plane| plane.reach).fold(1.0, f64::max);
+    let mut polygon = vec![
+        Vector2::new(-reach, -reach),
+        Vector2::new(reach, -reach),
+        Vector2::new(reach, reach),
+        Vector2::new(-reach, reach),
+    ];
+    for plane in planes {
+        let normal = plane.normal.xy();
+        let offset = plane.distance - plane.normal.z * z;
+        // a floor or ceiling either keeps the whole cut or none of it
+        if normal.norm() < 1e-9 {
+            if offset < 0.0 {
+                return vec![];
+            }
+            continue;
+        }
+        polygon = polygon::clip(&polygon, normal, offset);
+    }
+
+    polygon::dedup(&mut polygon, reach * 1e-12);
+    if polygon.len() < 3 {
+        return vec![];
+    }
+    // the polygon goes counterclockwise, so walking it backwards puts the
+    // outside on the left
+    (0..polygon.len())
+        .map(|i| Wall::new(polygon[(i + 1) % polygon.len()], polygon[i]))
+        .collect()
+}
@@ -0,0 +1,53 @@
+use alloc::vec::Vec;
+
+use nalgebra::Vector2;
+
+use crate::{Map, Wall};
+
+impl Map {
+    /// A map of `n_walls` pseudo-random walls with both coordinates of every
+    /// endpoint between `bounds.0` and `bounds.1`, for benchmarks and for
+    /// fuzzing the tree builder. The same seed gives the same map on every
+    /// platform. Walls may cross each other but never have zero length.
+    ///
+    /// # Panics
+    ///
+    /// If `bounds.0` is not less than `bounds.1`, since no wall then fits.
+    pub fn random(seed: u64, n_walls: usize, bounds: (f64, f64)) -> Map {
+        let (low, high) = bounds;
+        assert!(low < high, "random map bounds are empty");
+        let mut rng = SplitMix64(seed);
+        let mut point = || {
+            let mut coordinate = || low + (high - low) * rng.next_unit();
+            Vector2::new(coordinate(), coordinate())
+        };
+        let walls = (0..n_walls)
+            .map(|_| loop {
+                let wall = Wall::new(point(), point());
+                if !wall.is_degenerate() {
+                    break wall;
+                }
+            })
+            .collect::<Vec<Wall>>();
+        Map::from_walls(walls)
+    }
+}
+
+// the splitmix64 generator, which is tiny and gives the same numbers
+// everywhere
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [0, 1) from the top 53 bits
+    fn next_unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
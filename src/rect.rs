@@ -0,0 +1,89 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{wall::tolerance, BSPTree, Wall};
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// Walls of the tree with any part inside the axis-aligned rectangle from
+    /// `min` to `max`, edges included, such as for a rubber-band selection.
+    /// Subtrees on a side of a split that no corner of the rectangle is on,
+    /// or whose [bounds](BSPTree::bounds) miss the rectangle, are skipped.
+    pub fn query_rect(&self, min: Vector2<T>, max: Vector2<T>) -> Vec<&Wall<T>> {
+        let corners = [
+            min,
+            Vector2::new(max.x, min.y),
+            max,
+            Vector2::new(min.x, max.y),
+        ];
+        let mut out = vec![];
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            let (low, high) = node.bounds;
+            if (0..2).any(|axis| high[axis] < min[axis] || low[axis] > max[axis]) {
+                continue;
+            }
+            out.extend(node.walls().filter(|wall| overlaps_rect(wall, min, max)));
+            let offsets = corners.map(|corner| node.segment.signed_distance(corner));
+            if let Some(front) = node.front.as_ref() {
+                if offsets.iter().any(|offset| *offset >= T::zero()) {
+                    stack.push(front);
+                }
+            }
+            if let Some(behind) = node.behind.as_ref() {
+                if offsets.iter().any(|offset| *offset <= T::zero()) {
+                    stack.push(behind);
+                }
+            }
+        }
+        out
+    }
+}
+
+// whether the ray from `origin` along `dir` reaches the box from `min` to
+// `max`, grown by a little so rays hitting a wall at a corner of the box
+// aren't missed
+pub(crate) fn ray_reaches_box<T: RealField + Copy>(
+    origin: Vector2<T>,
+    dir: Vector2<T>,
+    (min, max): (Vector2<T>, Vector2<T>),
+) -> bool {
+    let slack = tolerance::<T>(1e-9) * (T::one() + (max - min).amax() + (origin - min).amax());
+    let (mut enter, mut exit) = (T::zero(), T::max_value().unwrap_or_else(T::one));
+    for axis in 0..2 {
+        let (low, high) = (min[axis] - slack, max[axis] + slack);
+        if dir[axis] == T::zero() {
+            if origin[axis] < low || origin[axis] > high {
+                return false;
+            }
+            continue;
+        }
+        let (a, b) = (
+            (low - origin[axis]) / dir[axis],
+            (high - origin[axis]) / dir[axis],
+        );
+        enter = enter.max(a.min(b));
+        exit = exit.min(a.max(b));
+    }
+    enter <= exit
+}
+
+// whether any point of `wall` is inside the rectangle, found by clipping the
+// wall's parameter range to the rectangle's slab along each axis
+fn overlaps_rect<T: RealField + Copy>(wall: &Wall<T>, min: Vector2<T>, max: Vector2<T>) -> bool {
+    let along = wall.p2 - wall.p1;
+    let (mut enter, mut exit) = (T::zero(), T::one());
+    for axis in 0..2 {
+        let (start, step) = (wall.p1[axis], along[axis]);
+        if step == T::zero() {
+            if start < min[axis] || start > max[axis] {
+                return false;
+            }
+            continue;
+        }
+        let (a, b) = ((min[axis] - start) / step, (max[axis] - start) / step);
+        enter = enter.max(a.min(b));
+        exit = exit.min(a.max(b));
+    }
+    enter <= exit
+}
@@ -0,0 +1,86 @@
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+
+use nalgebra::Vector2;
+
+use crate::{map, BSPTree};
+
+impl BSPTree {
+    /// A `width` by `height` RGB image of the tree from above, three bytes
+    /// per pixel with rows from the top, for checking the partition by eye.
+    /// Each convex region of the tree gets its own color and the walls are
+    /// drawn over the regions in black. The walls' bounds are fitted to the
+    /// image without stretching.
+    ///
+    /// The region of a pixel is found with [`BSPTree::locate`] at its center,
+    /// and is the side of the last returned split the center is on.
+    pub fn render_regions(&self, width: u32, height: u32) -> Vec<u8> {
+        let (width, height) = (width as usize, height as usize);
+        let mut out = vec![0; width * height * 3];
+        if out.is_empty() {
+            return out;
+        }
+        let (min, max) = map::bounds(self.iter());
+        let scale = ((max.x - min.x) / width as f64)
+            .max((max.y - min.y) / height as f64)
+            .max(1e-9);
+        // world position of the pixel grid's top left corner, centering the
+        // walls along the axis they don't fill
+        let origin = Vector2::new(
+            (min.x + max.x) / 2.0 - scale * width as f64 / 2.0,
+            (min.y + max.y) / 2.0 + scale * height as f64 / 2.0,
+        );
+
+        let nodes = self.preorder();
+        let children = BSPTree::preorder_children(&nodes);
+        let mut colors = BTreeMap::new();
+        for row in 0..height {
+            for column in 0..width {
+                let point = origin + Vector2::new(column as f64 + 0.5, -(row as f64 + 0.5)) * scale;
+                let region = region(&nodes, &children, point);
+                let next = colors.len();
+                let color = *colors.entry(region).or_insert_with(|| color(next));
+                let i = (row * width + column) * 3;
+                out[i..i + 3].copy_from_slice(&color);
+            }
+        }
+
+        for wall in self.iter() {
+            let pixel = |point: Vector2<f64>| (point - origin) / scale;
+            let (start, end) = (pixel(wall.p1), pixel(wall.p2));
+            let steps = (end - start).abs().max() as usize + 1;
+            for step in 0..=steps {
+                let at = start + (end - start) * (step as f64 / steps as f64);
+                let (column, row) = (at.x, -at.y);
+                if column >= 0.0 && row >= 0.0 && column < width as f64 && row < height as f64 {
+                    let i = (row as usize * width + column as usize) * 3;
+                    out[i..i + 3].fill(0);
+                }
+            }
+        }
+        out
+    }
+}
+
+// the preorder index of the node `BSPTree::locate` ends at for `point`, and
+// whether `point` is in front of it
+fn region(
+    nodes: &[(usize, &BSPTree)],
+    children: &[[Option<usize>; 2]],
+    point: Vector2<f64>,
+) -> (usize, bool) {
+    let mut i = 0;
+    loop {
+        let front = nodes[i].1.segment.in_front_point(&point);
+        match children[i][usize::from(!front)] {
+            Some(child) => i = child,
+            None => return (i, front),
+        }
+    }
+}
+
+// a light color for the `index`th region, scrambled so neighbouring regions
+// rarely look alike
+fn color(index: usize) -> [u8; 3] {
+    let bits = (index as u32).wrapping_add(1).wrapping_mul(0x9e37_79b9);
+    [bits >> 24, bits >> 16, bits >> 8].map(|channel| channel as u8 | 0x60)
+}
@@ -0,0 +1,76 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::RealField;
+
+use crate::{build::tree_create_with, BSPTree, BuildOptions, Map, Wall};
+
+/// The pieces each wall of a map was spliced into while building its tree,
+/// returned by [`Map::generate_tree_with_report`].
+#[derive(Debug, Clone)]
+pub struct SplitReport<T = f64> {
+    fragments: Vec<Vec<Wall<T>>>,
+    dropped: Vec<usize>,
+}
+
+impl<T: RealField + Copy> SplitReport<T> {
+    /// The pieces stored in the tree for the map's wall at `index`, in order
+    /// along the wall. A wall that wasn't split is its own only piece, and a
+    /// zero-length wall left out of the tree has none.
+    pub fn fragments(&self, index: usize) -> &[Wall<T>] {
+        &self.fragments[index]
+    }
+
+    /// Indices of the walls that were split, with the number of pieces each
+    /// became.
+    pub fn split_walls(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.fragments
+            .iter()
+            .enumerate()
+            .filter(|(_, pieces)| pieces.len() > 1)
+            .map(|(index, pieces)| (index, pieces.len()))
+    }
+
+    /// Indices of the [degenerate](Wall::is_degenerate) walls left out of
+    /// the tree, such as a wall between two copies of the same vertex.
+    /// [`Map::validate`] tells why each was left out.
+    pub fn dropped_walls(&self) -> &[usize] {
+        &self.dropped
+    }
+
+    /// How many more walls the tree holds than the map, zero if nothing was
+    /// split.
+    pub fn extra_fragments(&self) -> usize {
+        self.fragments
+            .iter()
+            .map(|pieces| pieces.len().saturating_sub(1))
+            .sum()
+    }
+}
+
+impl<T: RealField + Copy> Map<T> {
+    /// Like [`Map::generate_tree`], also reporting which walls were split and
+    /// into which pieces, and which were left out.
+    pub fn generate_tree_with_report(&self) -> (Option<BSPTree<T>>, SplitReport<T>) {
+        let (dropped, kept): (Vec<usize>, Vec<usize>) =
+            (0..self.walls.len()).partition(|&index| self.walls[index].is_degenerate());
+        let walls = kept
+            .iter()
+            .map(|&index| self.walls[index])
+            .collect::<Vec<_>>();
+        let mut fragments = vec![vec![]; self.walls.len()];
+        let tree = tree_create_with(
+            &walls,
+            BuildOptions::default(),
+            |origin, wall| fragments[kept[origin]].push(*wall),
+            &mut |_: usize, _: bool| {},
+        );
+        for pieces in &mut fragments {
+            pieces.sort_by(|a: &Wall<T>, b: &Wall<T>| {
+                a.u_offset
+                    .partial_cmp(&b.u_offset)
+                    .unwrap_or(core::cmp::Ordering::Equal)
+            });
+        }
+        (tree, SplitReport { fragments, dropped })
+    }
+}
@@ -0,0 +1,56 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::RealField;
+
+use crate::{
+    build::{place_walls, Place},
+    BuildOptions, Map, Thing, Wall,
+};
+
+impl<T: RealField + Copy> Map<T> {
+    /// Cuts the map in two along the line through `plane`, which needn't be
+    /// one of the map's walls, such as to stream a level in halves. Walls
+    /// crossing the line are spliced where they cross it, and each wall or
+    /// piece goes to the half on its side of the line, decided as when
+    /// building a tree, so the walls of both halves together cover the walls
+    /// of the map. Walls on the line go to the front half if they face the
+    /// same way as `plane`, and to the behind half otherwise.
+    ///
+    /// Things go to the half their position is on, and both halves keep all
+    /// the sectors so the walls' sector indices stay valid. Nothing is added
+    /// along the cut.
+    pub fn split_by(&self, plane: Wall<T>) -> (Map<T>, Map<T>) {
+        let mut walls = vec![plane];
+        walls.extend_from_slice(&self.walls);
+        let (mut front, mut behind) = (vec![], vec![]);
+        place_walls(
+            &walls,
+            0,
+            BuildOptions::default().tolerance(),
+            |_, wall, place| match place {
+                Place::Coplanar if wall.forward.dot(&plane.forward) > T::zero() => front.push(wall),
+                Place::Coplanar => behind.push(wall),
+                Place::Front => front.push(wall),
+                Place::Behind => behind.push(wall),
+            },
+        );
+        let (front_things, behind_things) = self
+            .things
+            .iter()
+            .partition(|thing| plane.in_front_point(&thing.pos));
+        (
+            self.with_parts(front, front_things),
+            self.with_parts(behind, behind_things),
+        )
+    }
+
+    fn with_parts(&self, walls: Vec<Wall<T>>, things: Vec<Thing<T>>) -> Map<T> {
+        Map {
+            walls,
+            sectors: self.sectors.clone(),
+            things,
+            #[cfg(feature = "std")]
+            modified: None,
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use nalgebra::RealField;
+
+use crate::BSPTree;
+
+/// Size and shape of a [`BSPTree`], for comparing split heuristics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeStats {
+    /// Number of nodes in the tree.
+    pub nodes: usize,
+    /// Number of nodes without children.
+    pub leaves: usize,
+    /// Number of walls stored in the tree, which is more than the number of
+    /// nodes when nodes hold coplanar walls or convex leaves.
+    pub walls: usize,
+    /// Depth of the deepest node, where the root has depth 0.
+    pub max_depth: usize,
+    /// Number of walls cut in two while building the tree, which is how many
+    /// more walls the tree holds than the map it was built from.
+    pub splits: usize,
+}
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// Counts the nodes, leaves, walls and splits of the tree and finds its
+    /// depth.
+    pub fn stats(&self) -> TreeStats {
+        let nodes = self.preorder();
+        TreeStats {
+            nodes: nodes.len(),
+            leaves: nodes
+                .iter()
+                .filter(|(_, node)| node.front.is_none() && node.behind.is_none())
+                .count(),
+            walls: nodes.iter().map(|(_, node)| node.walls().count()).sum(),
+            max_depth: nodes.iter().map(|(depth, _)| *depth).max().unwrap_or(0),
+            splits: nodes.iter().map(|(_, node)| node.splits).sum(),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use alloc::{format, string::String, string::ToString, vec::Vec};
+use core::fmt::Write;
+
+use nalgebra::Vector2;
+
+use crate::{map, BSPTree, Map, Wall};
+
+impl Map {
+    /// SVG drawing of the walls, each with a short tick from its midpoint in
+    /// the direction of `forward`. The view box fits the wall endpoints.
+    pub fn to_svg(&self) -> String {
+        let walls = self.walls.iter().map(|wall| (*wall, "black".to_string()));
+        svg(walls.collect())
+    }
+
+    /// Like [`Map::to_svg`], but draws the segments stored in `tree` colored
+    /// by their depth in it.
+    pub fn to_svg_with_tree(&self, tree: &BSPTree) -> String {
+        let walls = tree.preorder().into_iter().flat_map(|(depth, node)| {
+            let color = format!("hsl({},70%,40%)", depth * 47 % 360);
+            node.walls().map(move |wall| (*wall, color.clone()))
+        });
+        svg(walls.collect())
+    }
+}
+
+fn svg(walls: Vec<(Wall, String)>) -> String {
+    let (min, max) = if walls.is_empty() {
+        (Vector2::zeros(), Vector2::new(1.0, 1.0))
+    } else {
+        map::bounds(walls.iter().map(|(wall, _)| wall))
+    };
+    let size = (max - min).max().max(1e-9);
+    let margin = size * 0.05;
+    let stroke = size * 0.005;
+    let tick = size * 0.02;
+
+    // svg's y axis points down, so y is negated throughout
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+        min.x - margin,
+        -max.y - margin,
+        max.x - min.x + 2.0 * margin,
+        max.y - min.y + 2.0 * margin
+    );
+    for (wall, color) in &walls {
+        let _ = writeln!(
+            out,
+            r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+            wall.p1.x, -wall.p1.y, wall.p2.x, -wall.p2.y, color, stroke
+        );
+        let middle = wall.midpoint();
+        let end = middle + wall.normal() * tick;
+        let _ = writeln!(
+            out,
+            r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+            middle.x, -middle.y, end.x, -end.y, color, stroke
+        );
+    }
+    out += "</svg>\n";
+    out
+}
@@ -0,0 +1,102 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{BSPTree, Map, Thing};
+
+impl<T: RealField + Copy> Map<T> {
+    /// The things at most `radius` from `pos`, in the order of
+    /// [`Map::things`], found by checking every thing. This is the fallback
+    /// for a map without a tree; with one, sort the things once with
+    /// [`Map::index_things`] and query the [`ThingIndex`] instead, which gives
+    /// the same things without looking at those in regions out of reach.
+    pub fn things_near(&self, pos: Vector2<T>, radius: T) -> Vec<&Thing<T>> {
+        self.things
+            .iter()
+            .filter(|thing| (thing.pos - pos).norm() <= radius)
+            .collect()
+    }
+
+    /// Sorts the things of the map into the regions of `tree`, which should
+    /// be built from the map, for [`ThingIndex::things_near`]. This is the
+    /// way to find things near a point when the map has a tree; the index
+    /// borrows the map and the tree, so make it again after changing either.
+    pub fn index_things<'a>(&'a self, tree: &'a BSPTree<T>) -> ThingIndex<'a, T> {
+        let nodes = tree.preorder();
+        let children = BSPTree::preorder_children(&nodes);
+        let mut index = ThingIndex {
+            things: &self.things,
+            nodes: nodes.into_iter().map(|(_, node)| node).collect(),
+            children,
+            buckets: vec![],
+        };
+
+        for (i, thing) in self.things.iter().enumerate() {
+            let mut node = 0;
+            loop {
+                let side = usize::from(!index.nodes[node].segment.in_front_point(&thing.pos));
+                match index.children[node][side] {
+                    Some(child) => node = child,
+                    None => {
+                        index.buckets.push((2 * node + side, i));
+                        break;
+                    }
+                }
+            }
+        }
+        index.buckets.sort_unstable();
+        index
+    }
+}
+
+/// The things of a map sorted into the convex regions of its BSP tree, made
+/// by [`Map::index_things`], so queries only look at things in the regions
+/// they reach.
+#[derive(Debug, Clone)]
+pub struct ThingIndex<'a, T = f64> {
+    things: &'a [Thing<T>],
+    // the tree's nodes in preorder, and the indices of their front and
+    // behind children there
+    nodes: Vec<&'a BSPTree<T>>,
+    children: Vec<[Option<usize>; 2]>,
+    // pairs of region and thing index sorted by region, where region
+    // `2 * node + side` is the empty front (0) or behind (1) side of a node
+    buckets: Vec<(usize, usize)>,
+}
+
+impl<'a, T: RealField + Copy> ThingIndex<'a, T> {
+    /// Like [`Map::things_near`], but only the things in regions of the tree
+    /// that the circle of `radius` around `pos` reaches are checked.
+    pub fn things_near(&self, pos: Vector2<T>, radius: T) -> Vec<&'a Thing<T>> {
+        let mut found = vec![];
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let distance = self.nodes[node].segment.signed_distance(pos);
+            // things exactly on the line are behind it, as in `in_front_point`
+            let reaches = [
+                distance + radius > T::zero(),
+                distance - radius <= T::zero(),
+            ];
+            for (side, reaches) in reaches.into_iter().enumerate() {
+                if !reaches {
+                    continue;
+                }
+                if let Some(child) = self.children[node][side] {
+                    stack.push(child);
+                    continue;
+                }
+                let region = 2 * node + side;
+                let start = self.buckets.partition_point(|(r, _)| *r < region);
+                found.extend(
+                    self.buckets[start..]
+                        .iter()
+                        .take_while(|(r, _)| *r == region)
+                        .map(|(_, i)| *i)
+                        .filter(|i| (self.things[*i].pos - pos).norm() <= radius),
+                );
+            }
+        }
+        found.sort_unstable();
+        found.into_iter().map(|i| &self.things[i]).collect()
+    }
+}
@@ -0,0 +1,83 @@
+use nalgebra::{convert, RealField, Rotation2, Vector2};
+
+use crate::Map;
+
+impl<T: RealField + Copy> Map<T> {
+    /// Average of every wall endpoint, or zero for a map without walls.
+    pub fn centroid(&self) -> Vector2<T> {
+        if self.walls.is_empty() {
+            return Vector2::zeros();
+        }
+        let sum = self
+            .walls
+            .iter()
+            .fold(Vector2::zeros(), |sum, wall| sum + wall.p1 + wall.p2);
+        sum / convert::<_, T>(2.0 * self.walls.len() as f64)
+    }
+
+    /// Moves every wall and thing by `offset`. Each wall keeps its
+    /// `forward`, which a translation doesn't change. Sloped floors move
+    /// with the walls.
+    pub fn translate(&mut self, offset: Vector2<T>) {
+        for wall in &mut self.walls {
+            wall.p1 += offset;
+            wall.p2 += offset;
+            wall.floor_height -= wall.floor_slope.dot(&offset);
+        }
+        for sector in &mut self.sectors {
+            sector.floor_height -= sector.floor_slope.dot(&offset);
+        }
+        for thing in &mut self.things {
+            thing.pos += offset;
+        }
+    }
+
+    /// Translates the map so its [`Map::centroid`] is at the origin, where
+    /// coordinates keep the most precision.
+    pub fn recenter(&mut self) {
+        self.translate(-self.centroid());
+    }
+
+    /// Turns every wall and thing counterclockwise by `radians` about the
+    /// origin. Each wall's `forward` turns with it, so walls keep their
+    /// fronts, and things turn to keep facing the same way relative to them.
+    pub fn rotate(&mut self, radians: T) {
+        let rotation = Rotation2::new(radians);
+        for wall in &mut self.walls {
+            wall.p1 = rotation * wall.p1;
+            wall.p2 = rotation * wall.p2;
+            wall.forward = rotation * wall.forward;
+            wall.floor_slope = rotation * wall.floor_slope;
+        }
+        for sector in &mut self.sectors {
+            sector.floor_slope = rotation * sector.floor_slope;
+        }
+        for thing in &mut self.things {
+            thing.pos = rotation * thing.pos;
+            thing.angle += radians;
+        }
+    }
+
+    /// Scales every wall by `factor` about the origin. `forward` and the u
+    /// offset are scaled with the walls, which a negative factor turns half
+    /// way around, along with things. Heights are kept, so sloped floors get
+    /// steeper as the map shrinks.
+    pub fn scale(&mut self, factor: T) {
+        for wall in &mut self.walls {
+            wall.p1 *= factor;
+            wall.p2 *= factor;
+            wall.forward *= factor;
+            wall.u_offset *= factor.abs();
+            wall.floor_slope /= factor;
+        }
+        for sector in &mut self.sectors {
+            sector.floor_slope /= factor;
+        }
+        for thing in &mut self.things {
+            thing.pos *= factor;
+            if factor < T::zero() {
+                thing.angle += T::pi();
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use alloc::{vec, vec::Vec};
+use core::{cmp::Ordering, fmt};
+
+use nalgebra::{RealField, Vector2};
+
+use crate::{map, wall::tolerance, Map};
+
+/// A problem found by [`Map::validate`]. Walls are identified by their index
+/// in [`Map::walls`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapError {
+    /// The wall has a non-finite coordinate.
+    Degenerate { wall: usize },
+    /// Both endpoints of the wall are the same point.
+    ZeroLength { wall: usize },
+    /// Both walls join the same two points.
+    Duplicate { first: usize, second: usize },
+    /// The walls cross each other away from their endpoints.
+    Intersecting { first: usize, second: usize },
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Degenerate { wall } => {
+                write!(f, "wall {} has a non-finite coordinate", wall)
+            }
+            MapError::ZeroLength { wall } => write!(f, "wall {} has zero length", wall),
+            MapError::Duplicate { first, second } => {
+                write!(f, "walls {} and {} are duplicates", first, second)
+            }
+            MapError::Intersecting { first, second } => {
+                write!(f, "walls {} and {} intersect", first, second)
+            }
+        }
+    }
+}
+
+impl core::error::Error for MapError {}
+
+impl<T: RealField + Copy> Map<T> {
+    /// Checks the map for authoring mistakes that would make its BSP tree
+    /// wrong, returning every problem found.
+    pub fn validate(&self) -> Result<(), Vec<MapError>> {
+        let mut errors = vec![];
+        for (i, wall) in self.walls.iter().enumerate() {
+            let finite = [wall.p1, wall.p2]
+                .iter()
+                .all(|point| point.iter().all(|x| x.is_finite()));
+            if !finite {
+                errors.push(MapError::Degenerate { wall: i });
+            } else if wall.is_degenerate() {
+                errors.push(MapError::ZeroLength { wall: i });
+            }
+        }
+
+        for (i, first) in self.walls.iter().enumerate() {
+            for (j, second) in self.walls.iter().enumerate().skip(i + 1) {
+                if first.same_segment(second) {
+                    errors.push(MapError::Duplicate {
+                        first: i,
+                        second: j,
+                    });
+                } else if first.intersection_segment(second).is_some() {
+                    errors.push(MapError::Intersecting {
+                        first: i,
+                        second: j,
+                    });
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Points that are an endpoint of an odd number of walls, where a wall
+    /// dangles instead of closing a loop and a player could slip through.
+    /// Endpoints closer together than a billionth of the map's size count
+    /// as one point. The points are returned from left to right.
+    pub fn find_open_loops(&self) -> Vec<Vector2<T>> {
+        let mut points = self
+            .walls
+            .iter()
+            .flat_map(|wall| [wall.p1, wall.p2])
+            .collect::<Vec<_>>();
+        points.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal));
+        let (min, max) = map::bounds(&self.walls);
+        let epsilon = tolerance::<T>(1e-9) * (max - min).max().max(T::one());
+
+        let mut used = vec![false; points.len()];
+        let mut out = vec![];
+        for i in 0..points.len() {
+            if used[i] {
+                continue;
+            }
+            let mut count = 0;
+            for j in i..points.len() {
+                if points[j].x - points[i].x > epsilon {
+                    break;
+                }
+                if !used[j] && (points[j] - points[i]).norm() <= epsilon {
+                    used[j] = true;
+                    count += 1;
+                }
+            }
+            if count % 2 == 1 {
+                out.push(points[i]);
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,289 @@
+use alloc::{vec, vec::Vec};
+
+use nalgebra::{convert, RealField, Rotation2, Vector2};
+
+use crate::{frustum::Frustum, wall::tolerance, BSPTree, Camera, Wall};
+
+impl<T: RealField + Copy> BSPTree<T> {
+    /// Walls that could be visible from `camera_pos` inside the view cone of
+    /// `fov_radians` around `facing`, nearest first.
+    ///
+    /// Walls are visited front to back while keeping track of the angles of
+    /// the view already hidden by nearer one-sided walls, and a wall is left
+    /// out once every angle it spans is hidden. Two-sided walls are portals,
+    /// so they are returned when seen but hide nothing. The walk stops as
+    /// soon as the whole view is hidden.
+    pub fn visible_walls(
+        &self,
+        camera_pos: Vector2<T>,
+        facing: Vector2<T>,
+        fov_radians: T,
+    ) -> Vec<&Wall<T>> {
+        let facing = facing.try_normalize(T::zero()).unwrap_or_else(Vector2::x);
+        let half_fov = (fov_radians / convert(2.0)).min(T::pi());
+        let frustum = Frustum::new(camera_pos, facing, fov_radians);
+        let mut hidden = Coverage::default();
+        let mut out = vec![];
+        Self::walk_render_order(self, camera_pos, false, Some(&frustum), |wall, _| {
+            // each half of the cone is convex, so the angles of the clipped
+            // wall within it run from one end of it to the other
+            let mut visible = false;
+            for (from, to) in [(-half_fov, T::zero()), (T::zero(), half_fov)] {
+                let Some(span) = angular_span(wall, camera_pos, facing, from, to) else {
+                    continue;
+                };
+                if hidden.covers(span) {
+                    continue;
+                }
+                visible = true;
+                if !wall.two_sided {
+                    hidden.insert(span);
+                }
+            }
+            if visible {
+                out.push(wall);
+            }
+            !hidden.covers((-half_fov, half_fov))
+        });
+        out
+    }
+
+    /// Why each wall is or isn't returned by [`BSPTree::visible_walls`] for
+    /// the view of `camera`, for debugging. Every wall is checked, even after
+    /// the whole view is hidden, and the hidden parts of a wall are blamed on
+    /// the nearer one-sided walls covering them.
+    pub fn visibility_trace(&self, camera: &Camera<T>) -> VisibilityTrace {
+        let facing = camera.direction();
+        let half_fov = (camera.fov / convert(2.0)).min(T::pi());
+        let frustum = camera.frustum();
+        let mut hidden = Coverage::default();
+        // the spans hidden by each wall so far, with the wall's index
+        let mut occluders: Vec<((T, T), usize)> = vec![];
+        let mut walls = (0..self.wall_count)
+            .map(|_| WallTrace {
+                visibility: WallVisibility::OutsideFrustum,
+                occluders: vec![],
+            })
+            .collect::<Vec<_>>();
+        Self::walk_render_order(
+            self,
+            camera.position,
+            false,
+            Some(&frustum),
+            |wall, index| {
+                let spans = [(-half_fov, T::zero()), (T::zero(), half_fov)]
+                    .into_iter()
+                    .filter_map(|(from, to)| angular_span(wall, camera.position, facing, from, to))
+                    .collect::<Vec<_>>();
+                if spans.is_empty() {
+                    return true;
+                }
+                let trace = &mut walls[index];
+                trace.occluders = occluders
+                    .iter()
+                    .filter(|(span, _)| spans.iter().any(|wall_span| overlaps(*span, *wall_span)))
+                    .map(|(_, occluder)| *occluder)
+                    .collect();
+                trace.occluders.sort_unstable();
+                trace.occluders.dedup();
+                trace.visibility = if spans.iter().all(|span| hidden.covers(*span)) {
+                    WallVisibility::Occluded
+                } else if trace.occluders.is_empty() {
+                    WallVisibility::Visible
+                } else {
+                    WallVisibility::PartiallyVisible
+                };
+                if !wall.two_sided && trace.visibility != WallVisibility::Occluded {
+                    for span in spans {
+                        hidden.insert(span);
+                        occluders.push((span, index));
+                    }
+                }
+                true
+            },
+        );
+        VisibilityTrace { walls }
+    }
+
+    /// Walls with any part inside the view cone of `fov_radians` around
+    /// `facing` from `camera_pos`, each with the angle from `facing` to the
+    /// middle of the view angles that part covers, sorted by that angle, such
+    /// as to lay walls out on a radar. Angles are in radians in `-pi..=pi`,
+    /// counterclockwise from `facing`; with a full circle of view, a wall
+    /// behind the camera spans the jump from `pi` to `-pi` and gets the angle
+    /// of its middle. Unlike [`BSPTree::visible_walls`], walls hidden behind
+    /// others are kept.
+    pub fn walls_by_angle(
+        &self,
+        camera_pos: Vector2<T>,
+        facing: Vector2<T>,
+        fov_radians: T,
+    ) -> Vec<(T, Wall<T>)> {
+        let facing = facing.try_normalize(T::zero()).unwrap_or_else(Vector2::x);
+        let half_fov = (fov_radians / convert(2.0)).min(T::pi());
+        let mut out = self
+            .iter()
+            .filter_map(|wall| {
+                let right = angular_span(wall, camera_pos, facing, -half_fov, T::zero());
+                let left = angular_span(wall, camera_pos, facing, T::zero(), half_fov);
+                let angle = match (right, left) {
+                    (Some((low, high)), None) | (None, Some((low, high))) => {
+                        (low + high) / convert(2.0)
+                    }
+                    // a wall can't span half a turn, so one seen on both sides
+                    // of the facing direction either crosses it or, if the
+                    // cone is wide enough, the direction straight behind
+                    (Some((right_low, right_high)), Some((left_low, left_high))) => {
+                        if right_high >= -Coverage::<T>::slack()
+                            && left_low <= Coverage::<T>::slack()
+                        {
+                            (right_low + left_high) / convert(2.0)
+                        } else {
+                            let angle = (left_low + right_high + T::two_pi()) / convert(2.0);
+                            if angle > T::pi() {
+                                angle - T::two_pi()
+                            } else {
+                                angle
+                            }
+                        }
+                    }
+                    (None, None) => return None,
+                };
+                Some((angle, *wall))
+            })
+            .collect::<Vec<_>>();
+        out.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(core::cmp::Ordering::Equal));
+        out
+    }
+}
+
+/// What [`BSPTree::visibility_trace`] found for each wall of the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VisibilityTrace {
+    walls: Vec<WallTrace>,
+}
+
+impl VisibilityTrace {
+    /// The trace of the wall at `index` in [`BSPTree::iter`] order.
+    ///
+    /// # Panics
+    ///
+    /// If `index` isn't the index of a wall of the traced tree.
+    pub fn wall(&self, index: usize) -> &WallTrace {
+        &self.walls[index]
+    }
+
+    /// The trace of each wall, in [`BSPTree::iter`] order.
+    pub fn walls(&self) -> &[WallTrace] {
+        &self.walls
+    }
+}
+
+/// How much of one wall a camera sees, and what hides the rest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WallTrace {
+    pub visibility: WallVisibility,
+    /// Indices in [`BSPTree::iter`] order of the nearer one-sided walls
+    /// covering some of the view angles this wall spans, in increasing
+    /// order.
+    pub occluders: Vec<usize>,
+}
+
+/// Whether a camera sees a wall, as traced by [`BSPTree::visibility_trace`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallVisibility {
+    /// Nothing nearer covers any of the wall.
+    Visible,
+    /// Nearer walls cover some of the wall, but not all of it.
+    PartiallyVisible,
+    /// Nearer walls cover every angle the wall spans.
+    Occluded,
+    /// No part of the wall is inside the view cone.
+    OutsideFrustum,
+}
+
+// whether two ranges of view angles share more than an endpoint, or one
+// that is a single angle lies on the other
+fn overlaps<T: RealField + Copy>(a: (T, T), b: (T, T)) -> bool {
+    let slack = Coverage::<T>::slack();
+    let shared = a.1.min(b.1) - a.0.max(b.0);
+    shared > slack || (shared >= -slack && (a.1 - a.0 <= slack || b.1 - b.0 <= slack))
+}
+
+// the range of view angles from `facing` covered by the part of `wall`
+// inside the convex wedge of angles from `from` to `to`
+fn angular_span<T: RealField + Copy>(
+    wall: &Wall<T>,
+    apex: Vector2<T>,
+    facing: Vector2<T>,
+    from: T,
+    to: T,
+) -> Option<(T, T)> {
+    let (start, along) = (wall.p1 - apex, wall.p2 - wall.p1);
+    let (low, high) = (Rotation2::new(from) * facing, Rotation2::new(to) * facing);
+    // positive inside the wedge, which is counterclockwise from `low` and
+    // clockwise from `high`
+    let inside = |d: Vector2<T>| [low.perp(&d), d.perp(&high)];
+    let (mut enter, mut exit) = (T::zero(), T::one());
+    for (at_start, at_end) in inside(start).into_iter().zip(inside(start + along)) {
+        if at_start < T::zero() && at_end < T::zero() {
+            return None;
+        }
+        if at_start < T::zero() {
+            enter = enter.max(at_start / (at_start - at_end));
+        } else if at_end < T::zero() {
+            exit = exit.min(at_start / (at_start - at_end));
+        }
+    }
+    if enter > exit {
+        return None;
+    }
+    // measured from the middle of the wedge, so angles never come near the
+    // jump from -pi to pi
+    let middle_angle = (from + to) / convert(2.0);
+    let middle = Rotation2::new(middle_angle) * facing;
+    let angle = |t: T| {
+        let d = start + along * t;
+        (middle_angle + middle.perp(&d).atan2(middle.dot(&d))).clamp(from, to)
+    };
+    let (a, b) = (angle(enter), angle(exit));
+    Some((a.min(b), a.max(b)))
+}
+
+// sorted, disjoint ranges of view angles hidden so far
+struct Coverage<T> {
+    spans: Vec<(T, T)>,
+}
+
+impl<T> Default for Coverage<T> {
+    fn default() -> Self {
+        Coverage { spans: vec![] }
+    }
+}
+
+impl<T: RealField + Copy> Coverage<T> {
+    // walls sharing an endpoint leave no real gap between their spans
+    fn slack() -> T {
+        tolerance(1e-9)
+    }
+
+    fn covers(&self, (low, high): (T, T)) -> bool {
+        self.spans
+            .iter()
+            .any(|&(start, end)| start - Self::slack() <= low && high <= end + Self::slack())
+    }
+
+    fn insert(&mut self, (mut low, mut high): (T, T)) {
+        // remove every span touching the new one and put back their union
+        self.spans.retain(|&(start, end)| {
+            let touches = start - Self::slack() <= high && low <= end + Self::slack();
+            if touches {
+                low = low.min(start);
+                high = high.max(end);
+            }
+            !touches
+        });
+        let index = self.spans.partition_point(|&(start, _)| start < low);
+        self.spans.insert(index, (low, high));
+    }
+}
@@ -0,0 +1,527 @@
+use nalgebra::{convert, RealField, Scalar, Vector2, Vector3};
+
+// walls whose directions differ by a smaller sine than this are parallel
+const PARALLEL_EPSILON: f64 = 1e-9;
+// fraction of a wall's length closer than which to an end it isn't split
+const SLIVER_EPSILON: f64 = 1e-9;
+
+// `value` converted to `T`, raised to a few ulps of `T` where it is finer than
+// `T` can resolve, so tolerances scale to `f32`
+pub(crate) fn tolerance<T: RealField + Copy>(value: f64) -> T {
+    let value: T = convert(value);
+    value.max(T::default_epsilon() * convert(16.0))
+}
+
+/// Which side of a wall is its front, named by the order in which the walls
+/// of a room facing into it go around the room.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Winding {
+    /// The front is on the left going from `p1` to `p2`.
+    #[default]
+    CounterClockwise,
+    /// The front is on the right going from `p1` to `p2`.
+    Clockwise,
+}
+
+/// A line segment from `p1` to `p2` with a front side given by `forward`.
+///
+/// The fields are private because `forward` is derived from the endpoints;
+/// read them through [`Wall::p1`], [`Wall::p2`] and [`Wall::forward`].
+///
+/// Coordinates are `f64` unless another [`RealField`] such as `f32` is given
+/// for `T`.
+///
+/// With the `serde` feature `forward` is not serialized, and is recomputed
+/// from the endpoints and [`Wall::winding`] on deserialization.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(
+        from = "SerializedWall<T>",
+        into = "SerializedWall<T>",
+        bound(
+            serialize = "T: RealField + Copy + serde::Serialize",
+            deserialize = "T: RealField + Copy + serde::Deserialize<'de>"
+        )
+    )
+)]
+pub struct Wall<T = f64> {
+    pub(crate) p1: Vector2<T>,
+    pub(crate) p2: Vector2<T>,
+    pub(crate) forward: Vector2<T>,
+    pub(crate) floor_height: T,
+    pub(crate) ceiling_height: T,
+    pub(crate) floor_slope: Vector2<T>,
+    pub(crate) sector: Option<usize>,
+    pub(crate) two_sided: bool,
+    pub(crate) texture: u32,
+    pub(crate) u_offset: T,
+    pub(crate) light: T,
+    pub(crate) id: u32,
+}
+
+impl<T: RealField + Copy> Wall<T> {
+    /// A wall from `p1` to `p2` with its front on the left. Endpoints with an
+    /// infinite or NaN coordinate give a [degenerate](Wall::is_degenerate)
+    /// wall, which the tree builder leaves out.
+    pub fn new(p1: Vector2<T>, p2: Vector2<T>) -> Self {
+        let vec3 = (
+            Vector3::<T>::new(p1.x, p1.y, T::zero()),
+            Vector3::<T>::new(p2.x, p2.y, T::zero()),
+        );
+        let up = Vector3::<T>::z();
+        let forward = up.cross(&(vec3.1 - vec3.0));
+        let forward = Vector2::<T>::new(forward.x, forward.y);
+        Self {
+            p1,
+            p2,
+            forward,
+            floor_height: T::zero(),
+            ceiling_height: T::one(),
+            floor_slope: Vector2::zeros(),
+            sector: None,
+            two_sided: false,
+            texture: 0,
+            u_offset: T::zero(),
+            light: T::one(),
+            id: 0,
+        }
+    }
+
+    /// Like [`Wall::new`], with the front on the side `winding` names.
+    /// [`Wall::new`] uses [`Winding::CounterClockwise`].
+    pub fn with_winding(p1: Vector2<T>, p2: Vector2<T>, winding: Winding) -> Self {
+        let wall = Self::new(p1, p2);
+        match winding {
+            Winding::CounterClockwise => wall,
+            Winding::Clockwise => Wall {
+                forward: -wall.forward,
+                ..wall
+            },
+        }
+    }
+
+    /// The side of the wall its front is on.
+    pub fn winding(&self) -> Winding {
+        if self.forward.perp(&(self.p2 - self.p1)) > T::zero() {
+            Winding::Clockwise
+        } else {
+            Winding::CounterClockwise
+        }
+    }
+
+    /// The wall with its coordinates and heights converted to `U`.
+    pub fn cast<U: RealField + Copy>(&self) -> Wall<U> {
+        let convert = |x: T| nalgebra::convert::<_, U>(x.to_subset().unwrap_or(f64::NAN));
+        let point = |p: Vector2<T>| Vector2::new(convert(p.x), convert(p.y));
+        Wall {
+            p1: point(self.p1),
+            p2: point(self.p2),
+            forward: point(self.forward),
+            floor_height: convert(self.floor_height),
+            ceiling_height: convert(self.ceiling_height),
+            floor_slope: point(self.floor_slope),
+            sector: self.sector,
+            two_sided: self.two_sided,
+            texture: self.texture,
+            u_offset: convert(self.u_offset),
+            light: convert(self.light),
+            id: self.id,
+        }
+    }
+
+    pub fn p1(&self) -> Vector2<T> {
+        self.p1
+    }
+
+    pub fn p2(&self) -> Vector2<T> {
+        self.p2
+    }
+
+    /// Normal pointing towards the front side of the wall, as long as the
+    /// wall, including the pieces [`Wall::splice`] and
+    /// [`Map::merge_collinear`](crate::Map::merge_collinear) make; see
+    /// [`Wall::normal`] for a unit normal.
+    pub fn forward(&self) -> Vector2<T> {
+        self.forward
+    }
+
+    /// Unit normal pointing towards the front side of the wall, which is
+    /// [`Wall::forward`] scaled to length 1, or zero for a zero-length wall.
+    pub fn normal(&self) -> Vector2<T> {
+        self.forward
+            .try_normalize(T::zero())
+            .unwrap_or_else(Vector2::zeros)
+    }
+
+    pub fn length(&self) -> T {
+        (self.p2 - self.p1).norm()
+    }
+
+    /// Whether both endpoints are the same point, or either has an infinite
+    /// or NaN coordinate, which leaves the wall without a usable front side.
+    /// Trees are built without degenerate walls.
+    pub fn is_degenerate(&self) -> bool {
+        self.p1 == self.p2 || !self.p1.iter().chain(self.p2.iter()).all(|x| x.is_finite())
+    }
+
+    /// The point a fraction `t` of the way from `p1` to `p2`, so 0 gives `p1`
+    /// and 1 gives `p2`.
+    pub fn point_at(&self, t: T) -> Vector2<T> {
+        self.p1 + (self.p2 - self.p1) * t
+    }
+
+    pub fn midpoint(&self) -> Vector2<T> {
+        (self.p1 + self.p2) / convert::<_, T>(2.0)
+    }
+
+    /// Unit vector from `p1` towards `p2`, or zero for a zero-length wall.
+    pub fn direction(&self) -> Vector2<T> {
+        (self.p2 - self.p1)
+            .try_normalize(T::zero())
+            .unwrap_or_else(Vector2::zeros)
+    }
+
+    /// Floor height of the wall's sector at the origin, or 0 for walls
+    /// outside any sector. On a flat floor this is the height everywhere;
+    /// see [`Wall::floor_height_at`] for sloped ones.
+    pub fn floor_height(&self) -> T {
+        self.floor_height
+    }
+
+    /// How much the floor of the wall's sector rises per unit along x and
+    /// along y, as [`Sector::floor_slope`](crate::Sector::floor_slope). Zero
+    /// for flat floors and walls outside any sector.
+    pub fn floor_slope(&self) -> Vector2<T> {
+        self.floor_slope
+    }
+
+    /// Height of the floor of the wall's sector at `point`, such as one of
+    /// the wall's endpoints.
+    pub fn floor_height_at(&self, point: Vector2<T>) -> T {
+        self.floor_height + self.floor_slope.dot(&point)
+    }
+
+    /// Ceiling height of the wall's sector, or 1 for walls outside any sector.
+    pub fn ceiling_height(&self) -> T {
+        self.ceiling_height
+    }
+
+    /// Index of the sector this wall bounds in [`Map::sectors`](crate::Map::sectors).
+    pub fn sector(&self) -> Option<usize> {
+        self.sector
+    }
+
+    /// Two-sided walls are portals between regions: they still split the BSP
+    /// tree, but do not block sight and should not occlude what is behind
+    /// them.
+    pub fn two_sided(&self) -> bool {
+        self.two_sided
+    }
+
+    pub fn set_two_sided(&mut self, two_sided: bool) {
+        self.two_sided = two_sided;
+    }
+
+    /// Texture id of the wall, inherited by both halves when it is spliced.
+    pub fn texture(&self) -> u32 {
+        self.texture
+    }
+
+    pub fn set_texture(&mut self, texture: u32) {
+        self.texture = texture;
+    }
+
+    /// Distance along the original wall at which this wall starts, so texture
+    /// coordinates stay continuous across splices.
+    pub fn u_offset(&self) -> T {
+        self.u_offset
+    }
+
+    /// Light level of the wall from 0 for dark to 1 for fully lit, like a
+    /// Doom sector's brightness. Both halves of a spliced wall keep it.
+    pub fn light(&self) -> T {
+        self.light
+    }
+
+    pub fn set_light(&mut self, light: T) {
+        self.light = light;
+    }
+
+    /// Identifies the wall this one was spliced from, so the pieces of a wall
+    /// in a tree can be grouped back together. Map files and
+    /// [`Map::from_walls`](crate::Map::from_walls) number their walls from 0
+    /// in the order they are listed, and walls added with
+    /// [`Map::push_wall`](crate::Map::push_wall) or
+    /// [`Map::merge`](crate::Map::merge) take ids after the largest in the
+    /// map; [`Wall::new`] gives 0.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn set_sector(&mut self, index: usize, sector: &crate::Sector<T>) {
+        self.sector = Some(index);
+        self.floor_height = sector.floor_height;
+        self.ceiling_height = sector.ceiling_height;
+        self.floor_slope = sector.floor_slope;
+    }
+
+    fn intersection_params(&self, other: &Wall<T>) -> Option<(T, T)> {
+        let x1 = self.p1.x;
+        let y1 = self.p1.y;
+        let x2 = self.p2.x;
+        let y2 = self.p2.y;
+
+        let x3 = other.p1.x;
+        let y3 = other.p1.y;
+        let x4 = other.p2.x;
+        let y4 = other.p2.y;
+
+        let denominator = (x1 - x2) * (y3 - y4) - (y1 - y2) * (x3 - x4);
+
+        // the denominator is the sine of the angle between the walls scaled by
+        // both lengths, so compare the sine itself to stay independent of the
+        // scale of the map
+        if denominator.abs() <= tolerance::<T>(PARALLEL_EPSILON) * self.length() * other.length() {
+            return None;
+        }
+
+        let t = ((x1 - x3) * (y3 - y4) - (y1 - y3) * (x3 - x4)) / denominator;
+        let u = -((x1 - x2) * (y1 - y3) - (y1 - y2) * (x1 - x3)) / denominator;
+
+        if t.is_finite() && u.is_finite() {
+            Some((t, u))
+        } else {
+            None
+        }
+    }
+
+    /// Intersection of this wall with the infinite line through `plane`, which
+    /// is what BSP splitting needs. Crossings within a sliver of either end
+    /// are ignored, since splitting there would leave a piece too short to
+    /// have a reliable plane of its own.
+    pub fn intersection(&self, plane: &Wall<T>) -> Option<Vector2<T>> {
+        self.intersection_t(plane).map(|t| self.point_at(t))
+    }
+
+    /// Like [`Wall::intersection`], but returns how far along this wall the
+    /// crossing is as a fraction of its length, for [`Wall::point_at`]. The
+    /// distance from `p1` to the crossing is `t` times [`Wall::length`].
+    pub fn intersection_t(&self, plane: &Wall<T>) -> Option<T> {
+        let (t, _) = self.intersection_params(plane)?;
+
+        let sliver = tolerance::<T>(SLIVER_EPSILON);
+        if t > sliver && t < T::one() - sliver {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    /// Intersection of this wall with `other`, treating both as finite segments.
+    pub fn intersection_segment(&self, other: &Wall<T>) -> Option<Vector2<T>> {
+        let (t, u) = self.intersection_params(other)?;
+
+        if t > T::zero() && t < T::one() && u > T::zero() && u < T::one() {
+            Some(self.point_at(t))
+        } else {
+            None
+        }
+    }
+
+    /// Distance along `dir`, in multiples of its length, at which the ray from
+    /// `origin` hits this wall. Rays parallel to the wall never hit it, and a
+    /// ray starting on the wall does not hit that wall.
+    pub fn ray_intersection(&self, origin: Vector2<T>, dir: Vector2<T>) -> Option<T> {
+        let ray = Wall::new(origin, origin + dir);
+        let (t, u) = ray.intersection_params(self)?;
+
+        if t > tolerance(1e-9) && u >= T::zero() && u <= T::one() {
+            Some(t)
+        } else {
+            None
+        }
+    }
+
+    // where the line through `plane` cuts the wall, found from the signed
+    // distances of its endpoints so that a wall with endpoints on opposite
+    // sides is always cut however nearly parallel it is; cuts within a sliver
+    // of `epsilon` of the wall's length from either end are ignored as in
+    // `intersection`
+    pub(crate) fn split_point(&self, plane: &Wall<T>, epsilon: f64) -> Option<Vector2<T>> {
+        let (d1, d2) = (
+            plane.signed_distance(self.p1),
+            plane.signed_distance(self.p2),
+        );
+        self.split_point_at(d1, d2, epsilon)
+    }
+
+    // `split_point` given the signed distances `d1` and `d2` of the wall's
+    // endpoints from the plane
+    pub(crate) fn split_point_at(&self, d1: T, d2: T, epsilon: f64) -> Option<Vector2<T>> {
+        if (d1 > T::zero()) == (d2 > T::zero()) || d1 == T::zero() || d2 == T::zero() {
+            return None;
+        }
+        let t = d1 / (d1 - d2);
+        let sliver = tolerance::<T>(epsilon);
+        if t > sliver && t < T::one() - sliver {
+            Some(self.point_at(t))
+        } else {
+            None
+        }
+    }
+
+    /// Cuts the wall in two at `point`, each piece keeping the wall's front
+    /// side and other attributes, with its own [`Wall::forward`].
+    pub fn splice(&self, point: Vector2<T>) -> (Wall<T>, Wall<T>) {
+        (
+            self.with_ends(self.p1, point),
+            Wall {
+                u_offset: self.u_offset + (point - self.p1).norm(),
+                ..self.with_ends(point, self.p2)
+            },
+        )
+    }
+
+    // the wall moved to join `p1` and `p2`, keeping its winding and other
+    // attributes, with `forward` as long as the moved wall
+    pub(crate) fn with_ends(&self, p1: Vector2<T>, p2: Vector2<T>) -> Wall<T> {
+        Wall {
+            p1,
+            p2,
+            forward: Wall::with_winding(p1, p2, self.winding()).forward,
+            ..*self
+        }
+    }
+
+    // whether both endpoints of the wall lie on the line through `plane`,
+    // within `epsilon` of the longer wall's length
+    pub(crate) fn on_plane(&self, plane: &Wall<T>, epsilon: f64) -> bool {
+        let limit = tolerance::<T>(epsilon) * plane.length().max(self.length());
+        [self.p1, self.p2]
+            .iter()
+            .all(|point| plane.signed_distance(*point).abs() <= limit)
+    }
+
+    /// Distance from the line through the wall to `point`, positive in front
+    /// of the wall and negative behind it.
+    pub fn signed_distance(&self, point: Vector2<T>) -> T {
+        (point - self.p1).dot(&self.normal())
+    }
+
+    /// Whether the midpoint of `wall` is in front of this wall.
+    pub fn in_front(&self, wall: &Wall<T>) -> bool {
+        self.in_front_point(&wall.midpoint())
+    }
+
+    pub fn in_front_point(&self, point: &Vector2<T>) -> bool {
+        self.signed_distance(*point) > T::zero()
+    }
+
+    /// Whether a camera at `camera_pos` sees the front of the wall, which is
+    /// when the camera is [in front](Wall::in_front_point) of it. A camera
+    /// on the wall's line sees neither side.
+    pub fn faces(&self, camera_pos: Vector2<T>) -> bool {
+        self.in_front_point(&camera_pos)
+    }
+
+    /// Distance from `point` to the nearest point of the wall, which is one of
+    /// the endpoints if `point` is beyond either end.
+    pub fn distance_to_point(&self, point: Vector2<T>) -> T {
+        (point - self.closest_point(point)).norm()
+    }
+
+    // the point of the wall nearest to `point`
+    pub(crate) fn closest_point(&self, point: Vector2<T>) -> Vector2<T> {
+        let along = self.p2 - self.p1;
+        let length_squared = along.norm_squared();
+        if length_squared == T::zero() {
+            return self.p1;
+        }
+        let t = ((point - self.p1).dot(&along) / length_squared).clamp(T::zero(), T::one());
+        self.point_at(t)
+    }
+
+    /// Whether both walls join the same two points, in either direction.
+    pub fn same_segment(&self, other: &Wall<T>) -> bool {
+        (self.p1 == other.p1 && self.p2 == other.p2) || (self.p1 == other.p2 && self.p2 == other.p1)
+    }
+}
+
+/// Walls are equal if they have the same `p1` and the same `p2`, whatever
+/// their other attributes. A wall is not equal to its reverse; see
+/// [`Wall::same_segment`].
+impl<T: Scalar> PartialEq for Wall<T> {
+    fn eq(&self, other: &Wall<T>) -> bool {
+        self.p1 == other.p1 && self.p2 == other.p2
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(bound(
+    serialize = "T: RealField + Copy + serde::Serialize",
+    deserialize = "T: RealField + Copy + serde::Deserialize<'de>"
+))]
+struct SerializedWall<T: Scalar> {
+    p1: Vector2<T>,
+    p2: Vector2<T>,
+    floor_height: T,
+    ceiling_height: T,
+    #[serde(default = "Vector2::zeros")]
+    floor_slope: Vector2<T>,
+    sector: Option<usize>,
+    two_sided: bool,
+    texture: u32,
+    u_offset: T,
+    light: T,
+    #[serde(default)]
+    winding: Winding,
+    #[serde(default)]
+    id: u32,
+}
+
+#[cfg(feature = "serde")]
+impl<T: RealField + Copy> From<SerializedWall<T>> for Wall<T> {
+    fn from(wall: SerializedWall<T>) -> Self {
+        Wall {
+            floor_height: wall.floor_height,
+            ceiling_height: wall.ceiling_height,
+            floor_slope: wall.floor_slope,
+            sector: wall.sector,
+            two_sided: wall.two_sided,
+            texture: wall.texture,
+            u_offset: wall.u_offset,
+            light: wall.light,
+            id: wall.id,
+            ..Wall::with_winding(wall.p1, wall.p2, wall.winding)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: RealField + Copy> From<Wall<T>> for SerializedWall<T> {
+    fn from(wall: Wall<T>) -> Self {
+        SerializedWall {
+            p1: wall.p1,
+            p2: wall.p2,
+            floor_height: wall.floor_height,
+            ceiling_height: wall.ceiling_height,
+            floor_slope: wall.floor_slope,
+            sector: wall.sector,
+            two_sided: wall.two_sided,
+            texture: wall.texture,
+            u_offset: wall.u_offset,
+            light: wall.light,
+            winding: wall.winding(),
+            id: wall.id,
+        }
+    }
+}
@@ -0,0 +1,424 @@
+#![cfg(feature = "std")]
+
+use bamn::{nalgebra::Vector2, BSPTree, BuildOptions, FlatBSP, Map, Wall};
+
+// a square spiral of `n` walls winding inward, outermost first, so each wall
+// has every later one on the same side and the tree is `n` deep
+fn spiral(n: usize) -> Vec<Wall> {
+    let directions = [
+        Vector2::new(1.0, 0.0),
+        Vector2::new(0.0, 1.0),
+        Vector2::new(-1.0, 0.0),
+        Vector2::new(0.0, -1.0),
+    ];
+    let mut point = Vector2::zeros();
+    let mut walls = (0..n)
+        .map(|i| {
+            let next = point + directions[i % 4] * (i / 2 + 1) as f64;
+            let wall = Wall::new(point, next);
+            point = next;
+            wall
+        })
+        .collect::<Vec<_>>();
+    walls.reverse();
+    walls
+}
+
+// `n` parallel walls one above the other, which build a tree as deep as a long
+// corridor
+fn linear(n: usize) -> Vec<Wall> {
+    (0..n)
+        .map(|i| Wall::new(Vector2::new(0.0, i as f64), Vector2::new(1.0, i as f64)))
+        .collect()
+}
+
+// the binary format of the tree `generate_tree` builds from `walls` that
+// each have all later walls on one side, each wall a node holding the rest on
+// that side, written out directly instead of building, which takes time
+// quadratic in the depth
+fn chain_bytes(walls: &[Wall]) -> Vec<u8> {
+    let mut bytes = header();
+    bytes.extend((walls.len() as u32).to_le_bytes());
+    for (i, wall) in walls.iter().enumerate() {
+        let (p1, p2, forward) = (wall.p1(), wall.p2(), wall.forward());
+        let values = [
+            p1.x, p1.y, p2.x, p2.y, forward.x, forward.y, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0,
+        ];
+        values
+            .iter()
+            .for_each(|value| bytes.extend(value.to_le_bytes()));
+        // no sector, one-sided, texture and id 0, and no coplanar or leaf
+        // walls, splits or flags
+        for value in [u32::MAX, 0, 0, 0, 0, 0, 0, 0] {
+            bytes.extend(value.to_le_bytes());
+        }
+        let mut children = [u32::MAX; 2];
+        if let Some(next) = walls.get(i + 1) {
+            children[usize::from(!wall.in_front_point(&next.midpoint()))] = i as u32 + 1;
+        }
+        children
+            .iter()
+            .for_each(|child| bytes.extend(child.to_le_bytes()));
+    }
+    bytes
+}
+
+// the magic bytes and version every binary tree starts with
+fn header() -> Vec<u8> {
+    let mut bytes = b"BSPT".to_vec();
+    bytes.extend(BSPTree::BINARY_VERSION.to_le_bytes());
+    bytes
+}
+
+fn write(tree: &BSPTree) -> Vec<u8> {
+    let mut bytes = vec![];
+    tree.write_binary(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn deep_linear_tree_renders() {
+    let walls = linear(50_000);
+    let tree = BSPTree::read_binary(&mut &chain_bytes(&walls)[..]).unwrap();
+    let camera = Vector2::new(0.5, -1.0);
+    let mut order = tree.get_render_order(camera);
+    assert_eq!(tree.get_render_order_front_to_back(camera), walls);
+    order.reverse();
+    assert_eq!(order, walls);
+}
+
+#[test]
+fn built_linear_tree_renders() {
+    // building takes time quadratic in the depth, so the tree is shallower
+    // than the one read above, but still three times as deep as the
+    // recursive build could go before overflowing the test's stack
+    let walls = linear(3_000);
+    let tree = Map::from_walls(walls.clone()).generate_tree().unwrap();
+    assert_eq!(tree.stats().max_depth, walls.len() - 1);
+    let mut order = tree.get_render_order(Vector2::new(0.5, -1.0));
+    order.reverse();
+    assert_eq!(order, walls);
+}
+
+#[test]
+fn built_spiral_tree_clones_and_drops() {
+    // cloning and dropping walk the tree without recursing, so a tree as
+    // deep as the built linear one fits on the test's stack
+    let walls = spiral(3_000);
+    let tree = Map::from_walls(walls.clone()).generate_tree().unwrap();
+    assert_eq!(tree.stats().max_depth, walls.len() - 1);
+
+    let clone = tree.clone();
+    let camera = Vector2::new(0.5, 0.5);
+    let order = tree.get_render_order(camera);
+    assert_eq!(order.len(), walls.len());
+    assert_eq!(clone.get_render_order(camera), order);
+    assert_eq!(format!("{clone:?}"), format!("{tree:?}"));
+    drop(tree);
+    assert_eq!(clone.get_render_order(camera), order);
+    drop(clone);
+}
+
+// a 4 by 4 room with a corner at the origin
+fn room() -> Map {
+    Map::from_polygon(&[
+        Vector2::new(0.0, 0.0),
+        Vector2::new(4.0, 0.0),
+        Vector2::new(4.0, 4.0),
+        Vector2::new(0.0, 4.0),
+    ])
+}
+
+// an 8 by 8 room around a triangular pillar, with the pillar's walls first
+// so splitting on them cuts the room's
+fn pillar_room() -> Map {
+    let map = Map::from_polygons(
+        &[
+            Vector2::new(-4.0, -4.0),
+            Vector2::new(4.0, -4.0),
+            Vector2::new(4.0, 4.0),
+            Vector2::new(-4.0, 4.0),
+        ],
+        &[vec![
+            Vector2::new(-1.0, -1.0),
+            Vector2::new(1.0, -1.0),
+            Vector2::new(0.0, 1.0),
+        ]],
+    );
+    Map::from_walls(map.walls().iter().rev().copied().collect())
+}
+
+#[test]
+fn front_to_back_reverses_back_to_front() {
+    let tree = room().generate_tree().unwrap();
+    for camera in [Vector2::new(1.0, 1.0), Vector2::new(2.0, -3.0)] {
+        let mut back_to_front = tree.get_render_order(camera);
+        let front_to_back = tree.get_render_order_front_to_back(camera);
+        assert_eq!(front_to_back.len(), 4);
+        back_to_front.reverse();
+        assert_eq!(front_to_back, back_to_front);
+    }
+
+    // below the room the bottom wall hides the top one
+    let order = tree.get_render_order_front_to_back(Vector2::new(2.0, -3.0));
+    let position = |middle| {
+        order
+            .iter()
+            .position(|wall| wall.midpoint() == middle)
+            .unwrap()
+    };
+    assert!(position(Vector2::new(2.0, 0.0)) < position(Vector2::new(2.0, 4.0)));
+}
+
+#[test]
+fn binary_round_trip_keeps_render_order() {
+    let map = pillar_room();
+    let tree = map.generate_tree().unwrap();
+    let bytes = write(&tree);
+    let read = BSPTree::read_binary(&mut &bytes[..]).unwrap();
+    assert_eq!(write(&read), bytes);
+    for camera in [
+        Vector2::new(2.0, 3.0),
+        Vector2::new(-3.5, 0.0),
+        Vector2::new(0.0, -2.0),
+    ] {
+        assert_eq!(read.get_render_order(camera), tree.get_render_order(camera));
+    }
+}
+
+#[test]
+fn binary_read_rejects_truncated_input() {
+    // a count of two billion nodes with none following
+    let mut bytes = header();
+    bytes.extend([0xff, 0xff, 0xff, 0x7f]);
+    let error = BSPTree::read_binary(&mut &bytes[..]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    let bytes = write(&room().generate_tree().unwrap());
+    for len in [0, 3, 4, 8, 11, bytes.len() / 2, bytes.len() - 1] {
+        let error = BSPTree::read_binary(&mut &bytes[..len]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
+
+#[test]
+fn binary_read_rejects_other_formats_and_versions() {
+    let bytes = write(&room().generate_tree().unwrap());
+    assert!(bytes.starts_with(&header()));
+
+    let mut other = bytes.clone();
+    other[..4].copy_from_slice(b"BSPU");
+    let mut newer = bytes.clone();
+    newer[4..8].copy_from_slice(&(BSPTree::BINARY_VERSION + 1).to_le_bytes());
+    let mut zero = bytes;
+    zero[4..8].copy_from_slice(&0u32.to_le_bytes());
+    for bytes in [other, newer, zero] {
+        let error = BSPTree::read_binary(&mut &bytes[..]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn iter_visits_every_wall_in_the_stats() {
+    let map = pillar_room();
+    for options in [
+        BuildOptions::default(),
+        BuildOptions {
+            convex_leaves: true,
+            ..BuildOptions::default()
+        },
+    ] {
+        let tree = map.generate_tree_with_options(options).unwrap();
+        let stats = tree.stats();
+        assert_eq!(tree.iter().count(), stats.walls);
+        assert!(stats.splits > 0);
+        assert_eq!(stats.walls, map.walls().len() + stats.splits);
+    }
+}
+
+#[test]
+fn render_order_indices_look_up_the_same_walls() {
+    let tree = pillar_room().generate_tree().unwrap();
+    let flat = FlatBSP::from(&tree);
+    let walls = tree.iter().copied().collect::<Vec<_>>();
+    for camera in [Vector2::new(2.0, 3.0), Vector2::new(-3.5, 0.0)] {
+        let order = tree.get_render_order(camera);
+        let indices = tree.get_render_order_indices(camera);
+        assert_eq!(indices.iter().map(|i| walls[*i]).collect::<Vec<_>>(), order);
+        let indices = flat.render_order_indices(camera);
+        let looked_up = indices.iter().map(|i| flat.walls()[*i]);
+        assert_eq!(looked_up.collect::<Vec<_>>(), order);
+    }
+}
+
+#[test]
+fn render_order_indices_are_not_map_positions() {
+    let map = pillar_room();
+    let tree = map.generate_tree().unwrap();
+    let walls = tree.iter().copied().collect::<Vec<_>>();
+    // the split pieces make more stored walls than map walls, and the ids
+    // lead back from each to the wall it came from
+    assert!(walls.len() > map.walls().len());
+    let indices = tree.get_render_order_indices(Vector2::new(2.0, 3.0));
+    assert!(indices.iter().any(|&i| i >= map.walls().len()));
+    for i in indices {
+        let wall = walls[i];
+        let source = map.walls()[wall.id() as usize];
+        assert!(source.distance_to_point(wall.p1()) < 1e-9);
+        assert!(source.distance_to_point(wall.p2()) < 1e-9);
+    }
+}
+
+#[test]
+fn shuffled_render_orders_fail_the_check() {
+    let camera = Vector2::new(0.5, -3.0);
+    let tree = Map::from_walls(linear(3)).generate_tree().unwrap();
+    assert_eq!(tree.verify_order(camera), Ok(()));
+    let order = tree.get_render_order(camera);
+    assert_eq!(BSPTree::check_render_order(&order, camera), Ok(()));
+
+    // the middle wall drawn first hides part of the farthest, drawn last
+    let shuffled = [order[1], order[2], order[0]];
+    let error = BSPTree::check_render_order(&shuffled, camera).unwrap_err();
+    assert_eq!((error.occluder, error.occluded), (0, 2));
+    let reversed = order.iter().rev().copied().collect::<Vec<_>>();
+    assert!(BSPTree::check_render_order(&reversed, camera).is_err());
+}
+
+#[test]
+fn a_reused_buffer_matches_a_fresh_render_order() {
+    let tree = pillar_room().generate_tree().unwrap();
+    // stale walls left from an earlier frame are cleared
+    let mut buffer = linear(20);
+    for camera in [
+        Vector2::new(2.0, 3.0),
+        Vector2::new(-3.5, 0.0),
+        Vector2::new(0.0, -2.0),
+    ] {
+        tree.get_render_order_into(camera, &mut buffer);
+        assert_eq!(buffer, tree.get_render_order(camera));
+    }
+}
+
+#[test]
+fn a_tree_cut_off_at_max_depth_renders_in_order() {
+    let walls = linear(12);
+    let options = BuildOptions {
+        max_depth: Some(2),
+        ..BuildOptions::default()
+    };
+    let tree = Map::from_walls(walls.clone())
+        .generate_tree_with_options(options)
+        .unwrap();
+    let stats = tree.stats();
+    assert_eq!((stats.max_depth, stats.walls), (2, 12));
+    // the walls past the cut are sorted by distance, which puts parallel
+    // walls in order from either side
+    for camera in [
+        Vector2::new(0.5, -3.0),
+        Vector2::new(0.5, 20.0),
+        Vector2::new(3.0, 5.5),
+    ] {
+        assert_eq!(tree.get_render_order(camera).len(), walls.len());
+        assert_eq!(tree.verify_order(camera), Ok(()));
+    }
+}
+
+#[test]
+fn render_order_json_lists_every_wall_in_order() {
+    let tree = pillar_room().generate_tree().unwrap();
+    let camera = Vector2::new(2.0, 3.0);
+    let order = tree.get_render_order(camera);
+    let json: serde_json::Value = serde_json::from_str(&tree.render_order_json(camera)).unwrap();
+    let commands = json.as_array().unwrap();
+    assert_eq!(commands.len(), order.len());
+    for (command, wall) in commands.iter().zip(&order) {
+        let point = |key: &str| {
+            Vector2::new(
+                command[key][0].as_f64().unwrap(),
+                command[key][1].as_f64().unwrap(),
+            )
+        };
+        assert_eq!((point("p1"), point("p2")), (wall.p1(), wall.p2()));
+        assert_eq!(command["texture"], wall.texture());
+        assert_eq!(command["light"], wall.light());
+    }
+}
+
+#[test]
+fn culled_render_order_keeps_the_walls_facing_the_camera() {
+    let map = room();
+    let tree = map.generate_tree().unwrap();
+    // inside, every wall faces in towards the camera
+    let inside = Vector2::new(1.0, 3.0);
+    assert_eq!(
+        tree.get_render_order_culled(inside),
+        tree.get_render_order(inside)
+    );
+
+    // below the room the bottom wall shows its back
+    let below = Vector2::new(2.0, -3.0);
+    let middles = tree
+        .get_render_order_culled(below)
+        .iter()
+        .map(|wall| wall.midpoint())
+        .collect::<Vec<_>>();
+    assert_eq!(middles.len(), 3);
+    for middle in [(2.0, 4.0), (0.0, 2.0), (4.0, 2.0)] {
+        assert!(middles.contains(&Vector2::new(middle.0, middle.1)));
+    }
+
+    // two-sided walls are kept either way
+    let mut walls = map.walls().to_vec();
+    walls.iter_mut().for_each(|wall| wall.set_two_sided(true));
+    let tree = Map::from_walls(walls).generate_tree().unwrap();
+    assert_eq!(tree.get_render_order_culled(below).len(), 4);
+}
+
+#[test]
+fn every_node_is_bounded_by_the_walls_below_it() {
+    let tree = pillar_room().generate_tree().unwrap();
+    assert!(tree.stats().splits > 0);
+    let mut nodes = vec![&tree];
+    while let Some(node) = nodes.pop() {
+        // the smallest box holding the ends of every wall in the subtree
+        let (mut min, mut max) = (node.segment().p1(), node.segment().p1());
+        for wall in node.iter() {
+            for end in [wall.p1(), wall.p2()] {
+                min = min.inf(&end);
+                max = max.sup(&end);
+            }
+        }
+        assert_eq!(node.bounds(), (min, max));
+        nodes.extend(node.front());
+        nodes.extend(node.behind());
+    }
+    assert_eq!(
+        tree.bounds(),
+        (Vector2::new(-4.0, -4.0), Vector2::new(4.0, 4.0))
+    );
+}
+
+#[test]
+fn a_limited_render_order_keeps_the_nearest_walls() {
+    let tree = Map::from_walls(linear(8)).generate_tree().unwrap();
+    // below the walls, so the lowest is nearest
+    let camera = Vector2::new(0.5, -1.0);
+    let full = tree.get_render_order(camera);
+    for max in 0..=10 {
+        let limited = tree.get_render_order_limited(camera, max);
+        assert_eq!(limited.len(), max.min(8));
+        // still back to front, ending with the nearest wall
+        assert_eq!(limited, full[full.len() - limited.len()..]);
+        for wall in &limited {
+            assert!(wall.p1().y < max as f64);
+        }
+    }
+
+    // in a room, the tail of the full order is kept too
+    let tree = pillar_room().generate_tree().unwrap();
+    let camera = Vector2::new(3.0, -2.0);
+    let full = tree.get_render_order(camera);
+    let limited = tree.get_render_order_limited(camera, 3);
+    assert_eq!(limited, full[full.len() - 3..]);
+}
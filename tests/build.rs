@@ -0,0 +1,270 @@
+use bamn::{nalgebra::Vector2, BuildOptions, Map, MapError, SplitHeuristic, TreeStats, Wall};
+
+// `n` parallel walls one above the other
+fn linear(n: usize) -> Map {
+    Map::from_walls(
+        (0..n)
+            .map(|i| Wall::new(Vector2::new(0.0, i as f64), Vector2::new(1.0, i as f64)))
+            .collect(),
+    )
+}
+
+#[test]
+fn balanced_heuristic_reduces_depth() {
+    let map = linear(63);
+    let first = map.generate_tree_with(SplitHeuristic::First).unwrap();
+    let balanced = map.generate_tree_with(SplitHeuristic::Balanced).unwrap();
+    assert_eq!(first.stats().max_depth, 62);
+    // halving the walls at each node takes six levels
+    assert_eq!(balanced.stats().max_depth, 5);
+    assert_eq!(balanced.stats().walls, 63);
+}
+
+#[test]
+fn degenerate_walls_are_reported() {
+    // the second vertex is listed twice, so the wall between its copies has
+    // no length
+    let vertices = [(0.0, 0.0), (4.0, 0.0), (4.0, 0.0), (4.0, 4.0)];
+    let points = vertices.map(|(x, y)| Vector2::new(x, y));
+    let map = Map::from_walls(
+        (0..4)
+            .map(|i| Wall::new(points[i], points[(i + 1) % 4]))
+            .collect(),
+    );
+    let (tree, report) = map.generate_tree_with_report();
+    assert_eq!(report.dropped_walls(), [1]);
+    assert!(report.fragments(1).is_empty());
+    assert_eq!(tree.unwrap().stats().walls, 3);
+    assert_eq!(map.validate(), Err(vec![MapError::ZeroLength { wall: 1 }]));
+}
+
+#[test]
+fn convex_leaves_hold_convex_wall_sets() {
+    // a plus-shaped room, which splits into a convex corridor and arms
+    let corners = [
+        (-1.0, -3.0),
+        (1.0, -3.0),
+        (1.0, -1.0),
+        (3.0, -1.0),
+        (3.0, 1.0),
+        (1.0, 1.0),
+        (1.0, 3.0),
+        (-1.0, 3.0),
+        (-1.0, 1.0),
+        (-3.0, 1.0),
+        (-3.0, -1.0),
+        (-1.0, -1.0),
+    ];
+    let map = Map::from_polygon(&corners.map(|(x, y)| Vector2::new(x, y)));
+    let options = BuildOptions {
+        convex_leaves: true,
+        ..BuildOptions::default()
+    };
+    let tree = map.generate_tree_with_options(options).unwrap();
+    assert!(tree.stats().nodes < map.generate_tree().unwrap().stats().nodes);
+
+    let mut stack = vec![&tree];
+    let mut leaves = 0;
+    while let Some(node) = stack.pop() {
+        stack.extend(node.front().into_iter().chain(node.behind()));
+        if node.front().is_some() || node.behind().is_some() {
+            continue;
+        }
+        leaves += 1;
+        let walls = node.iter().collect::<Vec<_>>();
+        assert!(walls.len() > 1);
+        for plane in &walls {
+            for wall in &walls {
+                for point in [wall.p1(), wall.p2()] {
+                    assert!(plane.signed_distance(point) > -1e-9);
+                }
+            }
+        }
+    }
+    assert_eq!(leaves, 2);
+}
+
+#[test]
+fn stats_of_balanced_and_linear_trees() {
+    let map = linear(7);
+    let stats = map.generate_tree().unwrap().stats();
+    let linear = TreeStats {
+        nodes: 7,
+        leaves: 1,
+        walls: 7,
+        max_depth: 6,
+        splits: 0,
+    };
+    assert_eq!(stats, linear);
+    let stats = map
+        .generate_tree_with(SplitHeuristic::Balanced)
+        .unwrap()
+        .stats();
+    let balanced = TreeStats {
+        nodes: 7,
+        leaves: 4,
+        walls: 7,
+        max_depth: 2,
+        splits: 0,
+    };
+    assert_eq!(stats, balanced);
+
+    // a plus, where the first wall cuts the second in two
+    let plus = Map::from_walls(vec![
+        Wall::new(Vector2::new(-1.0, 0.0), Vector2::new(1.0, 0.0)),
+        Wall::new(Vector2::new(0.0, -1.0), Vector2::new(0.0, 1.0)),
+    ]);
+    let stats = plus.generate_tree().unwrap().stats();
+    assert_eq!((stats.nodes, stats.walls, stats.splits), (3, 3, 1));
+}
+
+#[test]
+fn reduced_epsilon_keeps_tiny_walls_apart() {
+    let scale = 1e-6;
+    let a = Wall::new(Vector2::new(0.0, 0.0), Vector2::new(scale, 0.0));
+    // a ten billionth of the walls' length above `a`, which the default
+    // tolerance counts as on its line
+    let y = scale * 1e-10;
+    let b = Wall::new(Vector2::new(0.0, y), Vector2::new(scale, y));
+    let map = Map::from_walls(vec![a, b]);
+    assert_eq!(map.generate_tree().unwrap().stats().nodes, 1);
+
+    let reduced = BuildOptions {
+        epsilon: Some(1e-12),
+        ..BuildOptions::default()
+    };
+    let tree = map.generate_tree_with_options(reduced).unwrap();
+    assert_eq!(tree.stats().nodes, 2);
+    let camera = Vector2::new(scale / 2.0, scale);
+    assert_eq!(tree.get_render_order(camera), map.walls());
+
+    // inserting with the same options places `b` as building does
+    let mut inserted = Map::from_walls(vec![a])
+        .generate_tree_with_options(reduced)
+        .unwrap();
+    inserted.insert_wall_with_options(b, reduced);
+    assert_eq!(inserted.describe(), tree.describe());
+    let mut inserted = Map::from_walls(vec![a]).generate_tree().unwrap();
+    inserted.insert_wall(b);
+    assert_eq!(inserted.stats().nodes, 1);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parallel_build_keeps_the_options() {
+    // each stretch of walls listed from its middle out, so splitting on the
+    // first wall halves them and shares out the halves
+    let walls = linear(4096).walls().to_vec();
+    let mut order = vec![];
+    let mut stretches = vec![(0, walls.len())];
+    while let Some((start, end)) = stretches.pop() {
+        if start < end {
+            let middle = (start + end) / 2;
+            order.push(walls[middle]);
+            stretches.push((middle + 1, end));
+            stretches.push((start, middle));
+        }
+    }
+    let map = Map::from_walls(order);
+    for options in [
+        BuildOptions {
+            epsilon: Some(1e-12),
+            ..BuildOptions::default()
+        },
+        BuildOptions {
+            max_depth: Some(4),
+            ..BuildOptions::default()
+        },
+    ] {
+        let parallel = map.generate_tree_parallel_with_options(options).unwrap();
+        let sequential = map.generate_tree_with_options(options).unwrap();
+        assert_eq!(parallel.describe(), sequential.describe());
+    }
+}
+
+#[test]
+fn report_of_an_unsplit_map_has_no_fragmentation() {
+    let corners = [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)];
+    let map = Map::from_polygon(&corners.map(|(x, y)| Vector2::new(x, y)));
+    let (tree, report) = map.generate_tree_with_report();
+    assert_eq!(tree.unwrap().stats().splits, 0);
+    assert_eq!(report.extra_fragments(), 0);
+    assert_eq!(report.split_walls().count(), 0);
+    for (i, wall) in map.walls().iter().enumerate() {
+        assert_eq!(report.fragments(i), [*wall]);
+    }
+
+    // where the first wall of a plus cuts the second in two
+    let plus = Map::from_walls(vec![
+        Wall::new(Vector2::new(-1.0, 0.0), Vector2::new(1.0, 0.0)),
+        Wall::new(Vector2::new(0.0, -1.0), Vector2::new(0.0, 1.0)),
+    ]);
+    let (_, report) = plus.generate_tree_with_report();
+    assert_eq!(report.extra_fragments(), 1);
+    assert_eq!(report.split_walls().collect::<Vec<_>>(), [(1, 2)]);
+}
+
+#[test]
+fn collinear_overlapping_walls_share_a_node() {
+    let a = Wall::new(Vector2::new(0.0, 0.0), Vector2::new(4.0, 0.0));
+    let b = Wall::new(Vector2::new(2.0, 0.0), Vector2::new(6.0, 0.0));
+    // facing the other way, but on the same line
+    let c = Wall::new(Vector2::new(5.0, 0.0), Vector2::new(1.0, 0.0));
+    let d = Wall::new(Vector2::new(0.0, 2.0), Vector2::new(4.0, 2.0));
+    let tree = Map::from_walls(vec![a, b, c, d]).generate_tree().unwrap();
+    let stats = tree.stats();
+    assert_eq!((stats.nodes, stats.walls, stats.splits), (2, 4, 0));
+    assert_eq!(tree.segment(), &a);
+    assert!(tree.behind().is_none());
+    assert_eq!(tree.front().unwrap().iter().collect::<Vec<_>>(), [&d]);
+}
+
+#[test]
+fn straddling_walls_are_split() {
+    let plane = Wall::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0));
+    let straddling: [Wall; 2] = [
+        // the midpoint just off the plane
+        Wall::new(Vector2::new(-10.0, -1.0), Vector2::new(10.0, 1.0002)),
+        // nearly parallel to it, crossing far from its ends
+        Wall::new(Vector2::new(-1000.0, -0.001), Vector2::new(1000.0, 0.001)),
+    ];
+    for straddling in straddling {
+        let tree = Map::from_walls(vec![plane, straddling])
+            .generate_tree()
+            .unwrap();
+        assert_eq!(tree.stats().splits, 1);
+        let front = tree.front().unwrap().segment();
+        let behind = tree.behind().unwrap().segment();
+        assert!(front.p2().y > 0.0 && front.p1().y.abs() < 1e-9);
+        assert!(behind.p1().y < 0.0 && behind.p2().y.abs() < 1e-9);
+        assert_eq!(front.p2(), straddling.p2());
+        assert_eq!(behind.p1(), straddling.p1());
+    }
+}
+
+#[test]
+fn the_profiler_hears_of_every_node_once() {
+    let mut calls = vec![];
+    let tree = linear(4)
+        .generate_tree_profiled(BuildOptions::default(), &mut |walls, spliced| {
+            calls.push((walls, spliced))
+        })
+        .unwrap();
+    // each wall is in front of the one before it, leaving one fewer each time
+    assert_eq!(calls, [(4, false), (3, false), (2, false), (1, false)]);
+    assert_eq!(calls.len(), tree.stats().nodes);
+
+    // a plus, where the root cuts the other wall into two leaves
+    let plus = Map::from_walls(vec![
+        Wall::new(Vector2::new(-1.0, 0.0), Vector2::new(1.0, 0.0)),
+        Wall::new(Vector2::new(0.0, -1.0), Vector2::new(0.0, 1.0)),
+    ]);
+    calls.clear();
+    let tree = plus
+        .generate_tree_profiled(BuildOptions::default(), &mut |walls, spliced| {
+            calls.push((walls, spliced))
+        })
+        .unwrap();
+    assert_eq!(calls, [(2, true), (1, false), (1, false)]);
+    assert_eq!(calls.len(), tree.stats().nodes);
+}
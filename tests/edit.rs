@@ -0,0 +1,82 @@
+use bamn::{nalgebra::Vector2, BuildOptions, Map, Wall};
+
+// a room around a pillar, with the pillar's walls first so building splits
+// some of the room's
+fn walls() -> Vec<Wall> {
+    let square = |size: f64| {
+        vec![
+            Vector2::new(-size, -size),
+            Vector2::new(size, -size),
+            Vector2::new(size, size),
+            Vector2::new(-size, size),
+        ]
+    };
+    let map = Map::from_polygons(&square(4.0), &[square(1.0)]);
+    map.walls().iter().rev().copied().collect()
+}
+
+const CAMERAS: [(f64, f64); 4] = [(2.0, 3.0), (-3.5, 0.0), (0.0, -2.0), (3.0, -3.0)];
+
+#[test]
+fn inserting_walls_matches_a_rebuild() {
+    let walls = walls();
+    let rebuilt = Map::from_walls(walls.clone()).generate_tree().unwrap();
+    // splitting on the walls in the same order, the walls inserted last take
+    // the same places that a rebuild gives them
+    let mut tree = Map::from_walls(walls[..4].to_vec())
+        .generate_tree()
+        .unwrap();
+    for wall in &walls[4..] {
+        tree.insert_wall(*wall);
+    }
+    assert_eq!(tree.describe(), rebuilt.describe());
+    assert_eq!(tree.stats(), rebuilt.stats());
+    for (x, y) in CAMERAS {
+        let camera = Vector2::new(x, y);
+        assert_eq!(
+            tree.get_render_order(camera),
+            rebuilt.get_render_order(camera)
+        );
+    }
+}
+
+#[test]
+fn removing_a_split_wall_removes_its_pieces() {
+    let far = Wall::new(Vector2::new(4.0, 5.0), Vector2::new(0.0, 5.0));
+    let cut = Wall::new(Vector2::new(2.0, -1.0), Vector2::new(2.0, 1.0));
+    let long = Wall::new(Vector2::new(0.0, 0.0), Vector2::new(4.0, 0.0));
+    for options in [
+        BuildOptions::default(),
+        BuildOptions {
+            convex_leaves: true,
+            ..BuildOptions::default()
+        },
+    ] {
+        // `cut` splits `long` in two below the root
+        let map = Map::from_walls(vec![far, cut, long]);
+        let [far, cut, long] = [0, 1, 2].map(|i| map.walls()[i]);
+        let mut tree = map.generate_tree_with_options(options).unwrap();
+        assert_eq!((tree.iter().count(), tree.stats().splits), (4, 1));
+        assert!(tree.remove_wall_with_options(&long, options));
+        assert_eq!(tree.iter().collect::<Vec<_>>(), [&far, &cut]);
+        assert_eq!(tree.stats().splits, 0);
+        assert!(!tree.remove_wall_with_options(&long, options));
+    }
+}
+
+#[test]
+fn removing_a_wall_keeps_other_walls_along_its_line() {
+    let far = Wall::new(Vector2::new(4.0, 5.0), Vector2::new(0.0, 5.0));
+    let left = Wall::new(Vector2::new(0.0, 0.0), Vector2::new(2.0, 0.0));
+    // a separate wall carrying on along the same line, and one lying on
+    // `left` that was drawn over it
+    let right = Wall::new(Vector2::new(2.0, 0.0), Vector2::new(4.0, 0.0));
+    let over = Wall::new(Vector2::new(0.5, 0.0), Vector2::new(1.5, 0.0));
+    let map = Map::from_walls(vec![far, left, right, over]);
+    let walls = map.walls().to_vec();
+    let mut tree = map.generate_tree().unwrap();
+    assert!(tree.remove_wall(&walls[1]));
+    let mut kept = tree.iter().copied().collect::<Vec<_>>();
+    kept.sort_by_key(|wall| wall.id());
+    assert_eq!(kept, [walls[0], walls[2], walls[3]]);
+}
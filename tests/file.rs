@@ -0,0 +1,413 @@
+#![cfg(feature = "std")]
+
+use bamn::{nalgebra::Vector2, Map, MapParseError, Thing, Wall, Winding};
+
+fn read(text: &str) -> Map {
+    Map::from_reader(text.as_bytes()).unwrap()
+}
+
+fn wall(p1: (f64, f64), p2: (f64, f64)) -> Wall {
+    Wall::new(Vector2::new(p1.0, p1.1), Vector2::new(p2.0, p2.1))
+}
+
+#[test]
+fn comments_and_blank_lines_are_skipped() {
+    let map = read(
+        "# a triangle\n\
+         0 0\n\
+         # between vertices\n\
+         \n\
+         1 0\n\
+         \x20\t\n\
+         0 1\n\
+         walls\n\
+         # between walls\n\
+         1 2\n\
+         \n\
+         2 3\n\
+         # another one\n\
+         3 1\n",
+    );
+    assert_eq!(
+        map.walls(),
+        [
+            wall((0.0, 0.0), (1.0, 0.0)),
+            wall((1.0, 0.0), (0.0, 1.0)),
+            wall((0.0, 1.0), (0.0, 0.0)),
+        ]
+    );
+}
+
+fn write(map: &Map) -> String {
+    let mut bytes = vec![];
+    map.write(&mut bytes).unwrap();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn write_round_trips() {
+    let map = read(include_str!("../map.txt"));
+    let text = write(&map);
+    assert_eq!(read(&text), map);
+    // the four corners of the diamond are each shared by two walls
+    let vertices = text.lines().skip_while(|line| *line != "verticies").skip(1);
+    assert_eq!(vertices.take_while(|line| *line != "walls").count(), 4);
+
+    let map = read(
+        "version 6\n\
+         vertices\n\
+         0 0\n\
+         2 0\n\
+         2 2\n\
+         0 2\n\
+         walls\n\
+         1 2\n\
+         2 3 1\n\
+         3 4 0 7\n\
+         4 1 0 0 0.5\n\
+         sectors\n\
+         0 3 1 2 3 4\n\
+         things\n\
+         1 1 2 1.5\n\
+         slopes\n\
+         1 0.25 0\n",
+    );
+    assert_eq!(read(&write(&map)), map);
+}
+
+fn error(text: &str) -> MapParseError {
+    Map::from_reader(text.as_bytes()).unwrap_err()
+}
+
+#[test]
+fn files_without_a_header_are_version_1() {
+    let map = read(
+        "0 0\n\
+         1 0\n\
+         0 1\n\
+         walls\n\
+         1 2\n\
+         2 3 1 4\n\
+         3 1\n",
+    );
+    assert_eq!(map.walls().len(), 3);
+    assert!(map.walls()[1].two_sided());
+    assert_eq!(map.walls()[1].texture(), 4);
+
+    // light levels came in version 3
+    let light = error("0 0\n1 0\nwalls\n1 2 0 0 0.5\n");
+    assert!(matches!(
+        light,
+        MapParseError::NeedsVersion {
+            line: 4,
+            version: 3
+        }
+    ));
+}
+
+#[test]
+fn version_2_files_have_sectors() {
+    let map = read(
+        "# a triangular room\n\
+         version 2\n\
+         0 0\n\
+         4 0\n\
+         0 4\n\
+         walls\n\
+         1 2\n\
+         2 3\n\
+         3 1\n\
+         sectors\n\
+         0.5 3 1 2 3\n",
+    );
+    assert_eq!(map.sectors().len(), 1);
+    for wall in map.walls() {
+        assert_eq!(wall.sector(), Some(0));
+        assert_eq!((wall.floor_height(), wall.ceiling_height()), (0.5, 3.0));
+    }
+}
+
+#[test]
+fn sections_newer_than_the_version_are_rejected() {
+    let cases = [
+        ("version 3\nvertices\n0 0\n", 2, 4),
+        ("version 4\n0 0\nthings\n0 0 1\n", 3, 5),
+        ("version 5\n0 0\nslopes\n", 3, 6),
+    ];
+    for (text, line, version) in cases {
+        match error(text) {
+            MapParseError::NeedsVersion {
+                line: found_line,
+                version: found_version,
+            } => assert_eq!((found_line, found_version), (line, version)),
+            error => panic!("{}: {}", text, error),
+        }
+    }
+    // and at the version that added them they are read
+    read("version 4\nvertices\n0 0\n");
+    read("version 5\nthings\n0 0 1\n");
+}
+
+#[test]
+fn progress_counts_toward_the_total() {
+    let path = format!("{}/progress.txt", env!("CARGO_TARGET_TMPDIR"));
+    std::fs::write(&path, "0 0\n1 0\n0 1\nwalls\n1 2\n# skipped\n2 3\n\n3 1\n").unwrap();
+    let mut calls = vec![];
+    let map = Map::from_file_with_progress(&path, |read, total| calls.push((read, total))).unwrap();
+    assert_eq!(map.walls().len(), 3);
+    assert_eq!(calls, [(1, 3), (2, 3), (3, 3)]);
+}
+
+#[test]
+fn flipping_the_winding_flips_every_front() {
+    let text = "0 0\n4 0\n1 3\nwalls\n1 2\n2 3\n3 1\n";
+    let counterclockwise = read(text);
+    let clockwise = Map::from_reader_with_winding(text.as_bytes(), Winding::Clockwise).unwrap();
+    let points = [(2.0, 1.0), (-1.0, 2.5), (3.0, -2.0), (5.0, 5.0)];
+    for (a, b) in counterclockwise.walls().iter().zip(clockwise.walls()) {
+        assert_eq!((a.p1(), a.p2()), (b.p1(), b.p2()));
+        let flipped = Wall::with_winding(a.p1(), a.p2(), Winding::Clockwise);
+        for (x, y) in points {
+            let point = Vector2::new(x, y);
+            assert_ne!(a.in_front_point(&point), b.in_front_point(&point));
+            assert_eq!(b.in_front_point(&point), flipped.in_front_point(&point));
+        }
+    }
+}
+
+#[test]
+fn field_counts_report_what_the_section_accepts() {
+    let cases = [
+        ("0 0\nwalls\n1\n", 3, 2, Some(4), 1),
+        ("0 0\nwalls\n1 1 0 0 0.5 2\n", 3, 2, Some(4), 6),
+        ("version 3\n0 0\nwalls\n1 1 0 0 0.5 2\n", 4, 2, Some(5), 6),
+        ("0 0\nwalls\n1 1\nsectors\n0.5\n", 5, 2, None, 1),
+        ("version 5\nthings\n0 0 1 0 2\n", 3, 3, Some(4), 5),
+        ("version 6\nslopes\n0 1\n", 3, 3, Some(3), 2),
+        ("0\n", 1, 2, Some(2), 1),
+    ];
+    for (text, line, min, max, found) in cases {
+        match error(text) {
+            MapParseError::FieldCount {
+                line: found_line,
+                min: found_min,
+                max: found_max,
+                found: found_fields,
+            } => assert_eq!(
+                (found_line, found_min, found_max, found_fields),
+                (line, min, max, found)
+            ),
+            error => panic!("{}: {}", text, error),
+        }
+    }
+
+    let messages = [
+        ("0\n", "line 1: expected 2 fields, found 1"),
+        ("0 0\nwalls\n1\n", "line 3: expected 2 to 4 fields, found 1"),
+        (
+            "0 0\nwalls\n1 1\nsectors\n0.5\n",
+            "line 5: expected at least 2 fields, found 1",
+        ),
+    ];
+    for (text, message) in messages {
+        assert_eq!(error(text).to_string(), message);
+    }
+}
+
+#[test]
+fn fields_split_on_tabs_and_runs_of_spaces() {
+    let spaces = read("0 0\n1 0\n0 1\nwalls\n1 2\n2 3\n3 1\n");
+    let mixed = read(
+        "0\t0\n\
+         1   0\n\
+         \x20 0 \t 1e0\t\n\
+         walls\n\
+         1\t2\n\
+         2  \t 3\n\
+         \t3     1\n",
+    );
+    assert_eq!(mixed, spaces);
+}
+
+#[test]
+fn non_finite_numbers_are_rejected() {
+    for (text, line) in [
+        ("0 0\nnan 1\n", 2),
+        ("0 0\n1 inf\n", 2),
+        ("version 2\n0 0\n1 0\nwalls\n1 2\nsectors\n-inf 1 1\n", 7),
+        ("version 3\n0 0\n1 0\nwalls\n1 2 0 0 NaN\n", 5),
+    ] {
+        match error(text) {
+            MapParseError::NonFinite { line: found } => assert_eq!(found, line),
+            error => panic!("{:?}: {}", text, error),
+        }
+    }
+
+    // a wall with such an end is degenerate, and left out of the tree
+    let broken = Wall::new(Vector2::new(0.0, 0.0), Vector2::new(f64::NAN, 1.0));
+    let infinite = Wall::new(Vector2::new(f64::INFINITY, 0.0), Vector2::new(1.0, 1.0));
+    assert!(broken.is_degenerate() && infinite.is_degenerate());
+    let kept = wall((0.0, 0.0), (1.0, 0.0));
+    let tree = Map::from_walls(vec![broken, kept, infinite])
+        .generate_tree()
+        .unwrap();
+    assert_eq!(tree.iter().collect::<Vec<_>>(), [&kept]);
+}
+
+#[test]
+fn a_quake_box_brush_becomes_four_walls() {
+    let brush = "{\n\
+         ( -64 -64 -16 ) ( -64 -63 -16 ) ( -64 -64 -15 ) __TB_empty 0 0 0 1 1\n\
+         ( -64 -64 -16 ) ( -64 -64 -15 ) ( -63 -64 -16 ) __TB_empty 0 0 0 1 1\n\
+         ( -64 -64 -16 ) ( -63 -64 -16 ) ( -64 -63 -16 ) __TB_empty 0 0 0 1 1\n\
+         ( 64 64 16 ) ( 64 65 16 ) ( 65 64 16 ) __TB_empty 0 0 0 1 1\n\
+         ( 64 64 16 ) ( 65 64 16 ) ( 64 64 17 ) __TB_empty 0 0 0 1 1\n\
+         ( 64 64 16 ) ( 64 64 17 ) ( 64 65 16 ) __TB_empty 0 0 0 1 1\n\
+         }\n";
+    let text = format!(
+        "// a 128 by 128 box, 32 high\n\
+         {{\n\"classname\" \"worldspawn\"\n{brush}}}\n\
+         {{\n\"classname\" \"func_door\"\n{brush}}}\n"
+    );
+    let map = Map::from_quake_map(text.as_bytes(), 0.0).unwrap();
+    assert_eq!(map.walls().len(), 4);
+    assert_eq!(
+        map.bounds(),
+        (Vector2::new(-64.0, -64.0), Vector2::new(64.0, 64.0))
+    );
+    for wall in map.walls() {
+        // on an edge of the box, facing out of it
+        let middle = wall.midpoint();
+        assert_eq!(middle.x.abs().max(middle.y.abs()), 64.0);
+        assert!(!wall.in_front_point(&Vector2::zeros()));
+    }
+
+    // above the box there is nothing to cut
+    let above = Map::from_quake_map(text.as_bytes(), 20.0).unwrap();
+    assert!(above.walls().is_empty());
+}
+
+#[test]
+fn reload_if_changed_reads_only_touched_files() {
+    let path = format!("{}/reload.txt", env!("CARGO_TARGET_TMPDIR"));
+    // written with a modification time `seconds` after the epoch, as file
+    // systems may not tell apart writes made close together
+    let write_at = |text: &str, seconds: u64| {
+        std::fs::write(&path, text).unwrap();
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(seconds);
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(time).unwrap();
+    };
+    write_at("0 0\n1 0\nwalls\n1 2\n", 1_000_000);
+    let mut map = Map::from_file(&path).unwrap();
+    assert!(!map.reload_if_changed(&path).unwrap());
+
+    write_at("0 0\n1 0\n0 1\nwalls\n1 2\n2 3\n3 1\n", 2_000_000);
+    assert!(map.reload_if_changed(&path).unwrap());
+    assert_eq!(map.walls().len(), 3);
+    assert!(!map.reload_if_changed(&path).unwrap());
+
+    // a broken file leaves the map as it was, and isn't read until it changes
+    write_at("0 0\nwalls\n1 9\n", 3_000_000);
+    assert!(map.reload_if_changed(&path).is_err());
+    assert_eq!(map.walls().len(), 3);
+    assert!(!map.reload_if_changed(&path).unwrap());
+}
+
+#[test]
+fn walls_may_come_before_the_vertices() {
+    let vertices_first = read("0 0\n1 0\n0 1\nwalls\n1 2\n2 3 1\n3 1\n");
+    let walls_first = read(
+        "version 4\n\
+         walls\n\
+         1 2\n\
+         2 3 1\n\
+         3 1\n\
+         vertices\n\
+         0 0\n\
+         1 0\n\
+         0 1\n",
+    );
+    assert_eq!(walls_first, vertices_first);
+    assert!(walls_first.walls()[1].two_sided());
+}
+
+#[test]
+fn vertices_read_with_or_without_a_header() {
+    let walls = "walls\n1 2\n2 3\n3 1\n";
+    let legacy = read(&format!("0 0\n2 0\n0 2\n{walls}"));
+    assert_eq!(legacy.walls().len(), 3);
+    for header in ["verticies", "vertices"] {
+        let text = format!("version 4\n{header}\n0 0\n2 0\n0 2\n{walls}");
+        assert_eq!(read(&text), legacy);
+    }
+    // and version 4 files may still leave the header out
+    assert_eq!(read(&format!("version 4\n0 0\n2 0\n0 2\n{walls}")), legacy);
+}
+
+#[test]
+fn things_are_read_with_their_positions_and_kinds() {
+    let map = read(
+        "version 5\n\
+         0 0\n\
+         4 0\n\
+         0 4\n\
+         walls\n\
+         1 2\n\
+         things\n\
+         # a player start facing up, then a monster\n\
+         1 1 1 1.5\n\
+         2.5 0.5 3004\n",
+    );
+    let things = [
+        Thing {
+            pos: Vector2::new(1.0, 1.0),
+            kind: 1,
+            angle: 1.5,
+        },
+        Thing {
+            pos: Vector2::new(2.5, 0.5),
+            kind: 3004,
+            angle: 0.0,
+        },
+    ];
+    assert_eq!(map.things(), things);
+    assert_eq!(read(&write(&map)).things(), things);
+}
+
+#[test]
+fn a_sloped_sector_rises_across_its_floor() {
+    let map = read(
+        "version 6\n\
+         0 0\n\
+         4 0\n\
+         0 4\n\
+         walls\n\
+         1 2\n\
+         2 3\n\
+         3 1\n\
+         sectors\n\
+         0.5 3 1 2 3\n\
+         slopes\n\
+         # up a quarter along x, down a half along y\n\
+         1 0.25 -0.5\n",
+    );
+    let sector = &map.sectors()[0];
+    assert_eq!(sector.floor_slope, Vector2::new(0.25, -0.5));
+    for (x, y, height) in [
+        (0.0, 0.0, 0.5),
+        (4.0, 0.0, 1.5),
+        (0.0, 4.0, -1.5),
+        (2.0, 1.0, 0.5),
+    ] {
+        assert_eq!(sector.floor_height_at(Vector2::new(x, y)), height);
+    }
+    // each wall's floor follows the slope of its sector between its ends
+    for wall in map.walls() {
+        assert_eq!(wall.floor_slope(), sector.floor_slope);
+        for end in [wall.p1(), wall.p2()] {
+            assert_eq!(wall.floor_height_at(end), sector.floor_height_at(end));
+        }
+    }
+    assert_eq!(map.walls()[0].floor_height_at(map.walls()[0].p2()), 1.5);
+}
@@ -0,0 +1,392 @@
+use bamn::{nalgebra::Vector2, Map, Thing, Wall, Winding};
+
+// the closed loop through `corners`
+fn polygon(corners: &[(f64, f64)]) -> Map {
+    Map::from_polygon(
+        &corners
+            .iter()
+            .map(|&(x, y)| Vector2::new(x, y))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[test]
+fn bounds_of_an_l_shaped_map() {
+    let map = polygon(&[
+        (-1.0, -2.0),
+        (5.0, -2.0),
+        (5.0, 0.0),
+        (1.0, 0.0),
+        (1.0, 3.0),
+        (-1.0, 3.0),
+    ]);
+    assert_eq!(
+        map.bounds(),
+        (Vector2::new(-1.0, -2.0), Vector2::new(5.0, 3.0))
+    );
+    let tree = map.generate_tree().unwrap();
+    assert_eq!(tree.bounds(), map.bounds());
+
+    let empty: Map = Map::from_walls(vec![]);
+    assert_eq!(empty.bounds(), (Vector2::zeros(), Vector2::zeros()));
+}
+
+#[test]
+fn a_player_in_a_corner_is_pushed_out_of_both_walls() {
+    let map = polygon(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+    let tree = map.generate_tree().unwrap();
+    let (pos, radius) = (Vector2::new(0.1, 0.2), 0.5);
+    let resolved = tree.resolve_collision(pos, radius);
+    // clear of both walls of the corner, and of every other wall
+    assert!((resolved - Vector2::new(0.5, 0.5)).norm() < 1e-9);
+    for wall in map.walls() {
+        assert!(wall.distance_to_point(resolved) >= radius - 1e-9);
+    }
+    // a player already clear isn't moved
+    let clear = Vector2::new(2.0, 1.0);
+    assert_eq!(tree.resolve_collision(clear, radius), clear);
+}
+
+// an 8 by 8 room around a 2 by 2 pillar, both centered on `center`, with the
+// pillar's walls first so building splits the room's
+fn pillar_room(center: (f64, f64)) -> Map {
+    let square = |size: f64| {
+        [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            .map(|(x, y)| Vector2::new(center.0 + x * size, center.1 + y * size))
+            .to_vec()
+    };
+    let map = Map::from_polygons(&square(4.0), &[square(1.0)]);
+    Map::from_walls(map.walls().iter().rev().copied().collect())
+}
+
+#[test]
+fn recentering_keeps_the_render_order() {
+    let map = pillar_room((1000.0, -2000.0));
+    let center = Vector2::new(1000.0, -2000.0);
+    assert_eq!(map.centroid(), center);
+    let mut recentered = map.clone();
+    recentered.recenter();
+    assert_eq!(recentered.centroid(), Vector2::zeros());
+
+    let tree = map.generate_tree().unwrap();
+    let recentered = recentered.generate_tree().unwrap();
+    assert!(tree.stats().splits > 0);
+    for (x, y) in [(2.0, 3.0), (-3.5, 0.0), (0.0, -2.0), (3.0, -3.0)] {
+        let camera = Vector2::new(x, y);
+        let shifted = tree
+            .get_render_order(camera + center)
+            .iter()
+            .map(|wall| (wall.p1() - center, wall.p2() - center))
+            .collect::<Vec<_>>();
+        let moved = recentered
+            .get_render_order(camera)
+            .iter()
+            .map(|wall| (wall.p1(), wall.p2()))
+            .collect::<Vec<_>>();
+        assert_eq!(moved, shifted);
+    }
+}
+
+fn thing(x: f64, y: f64) -> Thing {
+    Thing {
+        pos: Vector2::new(x, y),
+        kind: 1,
+        angle: 0.0,
+    }
+}
+
+#[test]
+fn things_near_a_point_are_those_within_the_radius() {
+    let mut map = pillar_room((0.0, 0.0));
+    assert!(map.things_near(Vector2::zeros(), 10.0).is_empty());
+    // inside the circle, exactly on it and outside it
+    for (x, y) in [(2.5, 0.5), (3.5, 0.0), (-2.5, 0.0)] {
+        map.push_thing(thing(x, y));
+    }
+    let near = map.things_near(Vector2::new(2.0, 0.0), 1.5);
+    assert_eq!(near, [&thing(2.5, 0.5), &thing(3.5, 0.0)]);
+    assert!(map.things_near(Vector2::new(0.0, 3.0), 1.0).is_empty());
+}
+
+#[test]
+fn the_thing_index_finds_the_things_a_full_scan_does() {
+    let mut map = pillar_room((0.0, 0.0));
+    let tree = map.generate_tree().unwrap();
+    let empty = map.index_things(&tree);
+    assert!(empty.things_near(Vector2::zeros(), 10.0).is_empty());
+
+    // a thing every half unit around the pillar, some on the lines of the
+    // tree's splits
+    for i in -7..=7 {
+        for j in -7..=7 {
+            let (x, y) = (f64::from(i) / 2.0, f64::from(j) / 2.0);
+            if x.abs() > 1.0 || y.abs() > 1.0 {
+                map.push_thing(thing(x, y));
+            }
+        }
+    }
+    let index = map.index_things(&tree);
+    for (x, y, radius) in [
+        (2.0, 0.0, 1.5),
+        (-3.0, -3.0, 0.5),
+        (0.0, 2.5, 3.0),
+        (1.5, 1.5, 0.0),
+        (0.0, 0.0, 10.0),
+    ] {
+        let pos = Vector2::new(x, y);
+        let near = map.things_near(pos, radius);
+        assert!(!near.is_empty());
+        assert_eq!(index.things_near(pos, radius), near);
+    }
+}
+
+#[test]
+fn rotating_a_full_turn_gives_back_the_map() {
+    let map = pillar_room((1.0, 2.0));
+    let mut turned = map.clone();
+    turned.rotate(std::f64::consts::TAU);
+    let close = |a: Vector2<f64>, b: Vector2<f64>| (a - b).norm() < 1e-9;
+    for (a, b) in map.walls().iter().zip(turned.walls()) {
+        assert!(close(a.p1(), b.p1()) && close(a.p2(), b.p2()));
+        assert!(close(a.normal(), b.normal()));
+    }
+
+    let tree = map.generate_tree().unwrap();
+    let turned = turned.generate_tree().unwrap();
+    for (x, y) in [(3.0, 5.0), (-2.5, 2.0), (1.0, 0.0), (4.0, -1.0)] {
+        let camera = Vector2::new(x, y);
+        let order = tree.get_render_order(camera);
+        let turned = turned.get_render_order(camera);
+        assert_eq!(order.len(), turned.len());
+        for (a, b) in order.iter().zip(&turned) {
+            assert!(close(a.p1(), b.p1()) && close(a.p2(), b.p2()));
+        }
+    }
+}
+
+#[test]
+fn merging_two_rooms_keeps_all_eight_walls() {
+    let room = polygon(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+    let mut merged = room.clone();
+    merged.merge(&room, Vector2::new(5.0, 0.0));
+    assert_eq!(merged.walls().len(), 8);
+    for (wall, moved) in room.walls().iter().zip(&merged.walls()[4..]) {
+        assert_eq!(moved.p1(), wall.p1() + Vector2::new(5.0, 0.0));
+        assert_eq!(moved.p2(), wall.p2() + Vector2::new(5.0, 0.0));
+    }
+    let tree = merged.generate_tree().unwrap();
+    assert_eq!(tree.get_render_order(Vector2::new(1.0, 1.0)).len(), 8);
+
+    // side by side the rooms share a wall, drawn once for each, which
+    // deduplicating keeps only once
+    let mut beside = room.clone();
+    beside.merge_deduplicated(&room, Vector2::new(2.0, 0.0));
+    assert_eq!(beside.walls().len(), 7);
+}
+
+#[test]
+fn the_shared_wall_of_two_rooms_becomes_one_portal() {
+    let room = polygon(&[(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)]);
+    let mut rooms = room.clone();
+    rooms.merge(&room, Vector2::new(2.0, 0.0));
+    assert_eq!(rooms.walls().len(), 8);
+    rooms.dedup_coincident();
+    assert_eq!(rooms.walls().len(), 7);
+
+    // the first room's side of the border is kept, now two-sided
+    let portals = rooms
+        .walls()
+        .iter()
+        .filter(|wall| wall.two_sided())
+        .collect::<Vec<_>>();
+    assert_eq!(portals.len(), 1);
+    assert_eq!(
+        (portals[0].p1(), portals[0].p2()),
+        (Vector2::new(2.0, 0.0), Vector2::new(2.0, 2.0))
+    );
+    assert_eq!(&rooms.walls()[..4], room.walls());
+
+    // and running it again finds nothing more to merge
+    let before = rooms.clone();
+    rooms.dedup_coincident();
+    assert_eq!(rooms, before);
+}
+
+#[test]
+fn reversed_walls_facing_the_same_side_are_kept() {
+    let (a, b) = (Vector2::new(0.0, 0.0), Vector2::new(2.0, 0.0));
+    let wall = Wall::new(a, b);
+    // drawn the other way round with its front on the same side
+    let reversed = Wall::with_winding(b, a, Winding::Clockwise);
+    assert_eq!(reversed.forward(), wall.forward());
+
+    let mut map = Map::from_walls(vec![wall, reversed]);
+    let before = map.clone();
+    map.dedup_coincident();
+    assert_eq!(map, before);
+    assert!(map.walls().iter().all(|wall| !wall.two_sided()));
+
+    // facing away from one another they are one border
+    let mut map = Map::from_walls(vec![wall, Wall::new(b, a)]);
+    map.dedup_coincident();
+    assert_eq!(map.walls().len(), 1);
+    assert!(map.walls()[0].two_sided());
+}
+
+#[test]
+fn splitting_a_room_cuts_the_walls_crossing_the_line() {
+    let room = polygon(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+    // up the line x = 1, with the left of it in front
+    let plane = Wall::new(Vector2::new(1.0, 0.0), Vector2::new(1.0, 1.0));
+    let (front, behind) = room.split_by(plane);
+
+    // the floor and ceiling walls are each cut in two, one piece a side
+    assert_eq!((front.walls().len(), behind.walls().len()), (3, 3));
+    for wall in front.walls() {
+        assert!(wall.p1().x <= 1.0 && wall.p2().x <= 1.0);
+    }
+    for wall in behind.walls() {
+        assert!(wall.p1().x >= 1.0 && wall.p2().x >= 1.0);
+    }
+    let length = |map: &Map| map.walls().iter().map(|wall| wall.length()).sum::<f64>();
+    assert_eq!(length(&front) + length(&behind), length(&room));
+    assert_eq!((length(&front), length(&behind)), (6.0, 10.0));
+
+    // a line missing the room leaves it whole on one side
+    let outside = Wall::new(Vector2::new(-1.0, 0.0), Vector2::new(-1.0, 1.0));
+    let (front, behind) = room.split_by(outside);
+    assert!(front.walls().is_empty());
+    assert_eq!(behind.walls(), room.walls());
+}
+
+#[test]
+fn a_square_polygon_faces_in_either_way_round() {
+    let corners = [(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)];
+    let mut clockwise = corners;
+    clockwise.reverse();
+    for corners in [corners, clockwise] {
+        let map = polygon(&corners);
+        assert_eq!(map.walls().len(), 4);
+        for (i, wall) in map.walls().iter().enumerate() {
+            let (x, y) = corners[i];
+            let (next_x, next_y) = corners[(i + 1) % 4];
+            assert_eq!(
+                (wall.p1(), wall.p2()),
+                (Vector2::new(x, y), Vector2::new(next_x, next_y))
+            );
+            // the normal points from the wall toward the middle of the room
+            let inward = Vector2::new(1.0, 1.0) - wall.midpoint();
+            assert_eq!(wall.normal(), inward);
+            assert!(wall.in_front_point(&Vector2::new(1.0, 1.0)));
+        }
+    }
+}
+
+#[test]
+fn a_square_hole_faces_out_into_the_room() {
+    let square = |size: f64| {
+        [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            .map(|(x, y)| Vector2::new(x * size, y * size))
+            .to_vec()
+    };
+    // the hole's points go the same way round as the room's
+    let map = Map::from_polygons(&square(4.0), &[square(1.0)]);
+    assert_eq!(map.walls().len(), 8);
+    let (room, hole) = map.walls().split_at(4);
+    for wall in room {
+        assert!(wall.in_front_point(&(wall.midpoint() * 0.5)));
+    }
+    for wall in hole {
+        // in front is the room around the hole, behind is the hole itself
+        assert!(wall.in_front_point(&(wall.midpoint() * 2.0)));
+        assert!(!wall.in_front_point(&Vector2::zeros()));
+    }
+
+    // building keeps all eight, and a camera in the room sees them all
+    let tree = map.generate_tree().unwrap();
+    assert_eq!(tree.get_render_order(Vector2::new(3.0, 0.0)).len(), 8);
+    // the walls round the hole block the view across it, but not past it
+    assert!(!tree.line_of_sight(Vector2::new(3.0, 0.0), Vector2::new(-3.0, 0.0)));
+    assert!(tree.line_of_sight(Vector2::new(3.0, 3.0), Vector2::new(-3.0, 3.0)));
+}
+
+#[test]
+fn welding_joins_corners_a_rounding_error_apart() {
+    // two rooms side by side whose shared border is drawn 1e-9 apart
+    let (x, gap) = (2.0, 1e-9);
+    let left = polygon(&[(0.0, 0.0), (x, 0.0), (x, 2.0), (0.0, 2.0)]);
+    let right = polygon(&[(x + gap, 0.0), (4.0, 0.0), (4.0, 2.0), (x + gap, 2.0)]);
+    let mut rooms = left.clone();
+    rooms.merge(&right, Vector2::zeros());
+    let mut unwelded = rooms.clone();
+    unwelded.dedup_coincident();
+    assert_eq!(unwelded.walls().len(), 8);
+
+    rooms.weld_vertices(1e-6);
+    assert_eq!(rooms.walls().len(), 8);
+    // every corner is now shared exactly by the two walls meeting there
+    for wall in rooms.walls() {
+        let meeting = |point| {
+            rooms
+                .walls()
+                .iter()
+                .filter(|other| other.p1() == point || other.p2() == point)
+                .count()
+        };
+        assert!(meeting(wall.p1()) >= 2 && meeting(wall.p2()) >= 2);
+        assert!(wall.p1().x == x || (wall.p1().x - x).abs() > 1e-6);
+    }
+    // so the border is now found to be shared
+    rooms.dedup_coincident();
+    assert_eq!(rooms.walls().len(), 7);
+
+    // a wall shorter than the tolerance is welded away
+    let mut tiny = left.clone();
+    tiny.push_wall(Vector2::new(1.0, 1.0), Vector2::new(1.0 + gap, 1.0));
+    tiny.weld_vertices(1e-6);
+    assert_eq!(tiny.walls(), left.walls());
+}
+
+#[test]
+fn a_hole_takes_its_area_out_of_the_room() {
+    let unit = polygon(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+    assert_eq!(unit.enclosed_area(), 1.0);
+    // the way round and the facing of the walls don't matter
+    let clockwise = polygon(&[(0.0, 1.0), (1.0, 1.0), (1.0, 0.0), (0.0, 0.0)]);
+    assert_eq!(clockwise.enclosed_area(), 1.0);
+
+    // an 8 by 8 room less its 2 by 2 pillar
+    assert_eq!(pillar_room((3.0, -1.0)).enclosed_area(), 60.0);
+
+    // a dangling wall encloses nothing
+    let mut open = unit.clone();
+    open.push_wall(Vector2::new(2.0, 0.0), Vector2::new(2.0, 1.0));
+    assert_eq!(open.enclosed_area(), 1.0);
+}
+
+#[test]
+fn nested_squares_trace_two_loops() {
+    let map = pillar_room((0.0, 0.0));
+    // shuffled, and with some walls turned round, as an editor might save them
+    let mut walls = map.walls().to_vec();
+    walls.swap(0, 5);
+    walls.swap(2, 7);
+    walls[3] = Wall::new(walls[3].p2(), walls[3].p1());
+    walls[6] = Wall::new(walls[6].p2(), walls[6].p1());
+    let map = Map::from_walls(walls);
+
+    let mut loops = map.loops();
+    assert_eq!(loops.len(), 2);
+    assert!(map.open_chains().is_empty());
+    // the room first, then the pillar
+    loops.sort_by(|a, b| b[0].norm().total_cmp(&a[0].norm()));
+    for (points, size) in loops.iter().zip([4.0, 1.0]) {
+        assert_eq!(points.len(), 4);
+        // consecutive corners are joined by a side of their square
+        for (i, corner) in points.iter().enumerate() {
+            assert_eq!(corner.x.abs().max(corner.y.abs()), size);
+            let next = points[(i + 1) % points.len()];
+            assert_eq!((next - corner).norm(), 2.0 * size);
+        }
+    }
+}
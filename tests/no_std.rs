@@ -0,0 +1,21 @@
+use std::process::Command;
+
+// without `std` the crate is `no_std` and only needs `alloc`, which a build
+// with the default features can't show, so the library is checked on its own
+#[test]
+fn the_library_builds_without_std() {
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "check",
+            "--lib",
+            "--no-default-features",
+            "--features",
+            "serde",
+        ])
+        .arg("--target-dir")
+        .arg(concat!(env!("CARGO_TARGET_TMPDIR"), "/no_std"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
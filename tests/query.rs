@@ -0,0 +1,128 @@
+use bamn::{nalgebra::Vector2, BSPTree, Map, Wall};
+
+fn wall(p1: (f64, f64), p2: (f64, f64)) -> Wall {
+    Wall::new(Vector2::new(p1.0, p1.1), Vector2::new(p2.0, p2.1))
+}
+
+fn tree(walls: Vec<Wall>) -> BSPTree {
+    Map::from_walls(walls).generate_tree().unwrap()
+}
+
+// a 4 by 4 room with a corner at the origin
+fn room() -> BSPTree {
+    Map::from_polygon(&[
+        Vector2::new(0.0, 0.0),
+        Vector2::new(4.0, 0.0),
+        Vector2::new(4.0, 4.0),
+        Vector2::new(0.0, 4.0),
+    ])
+    .generate_tree()
+    .unwrap()
+}
+
+#[test]
+fn raycast_hits_the_walls_of_a_box() {
+    let tree = room();
+    let cases = [
+        ((1.0, 2.0), (1.0, 0.0), (4.0, 2.0)),
+        ((1.0, 2.0), (0.0, -1.0), (1.0, 0.0)),
+        ((3.0, 1.0), (-2.0, 0.0), (0.0, 1.0)),
+        ((3.0, 1.0), (0.0, 0.5), (3.0, 4.0)),
+        ((1.0, 1.0), (1.0, 2.0), (2.5, 4.0)),
+    ];
+    for (origin, dir, hit) in cases {
+        let origin = Vector2::new(origin.0, origin.1);
+        let (point, wall) = tree.raycast(origin, Vector2::new(dir.0, dir.1)).unwrap();
+        assert!((point - Vector2::new(hit.0, hit.1)).norm() < 1e-12);
+        assert!(wall.distance_to_point(point) < 1e-12);
+    }
+}
+
+#[test]
+fn locate_separates_the_quadrants_of_a_plus() {
+    let tree = tree(vec![
+        wall((-2.0, 0.0), (2.0, 0.0)),
+        wall((0.0, -2.0), (0.0, 2.0)),
+    ]);
+    // the side of each bounding wall that the point is on
+    let sides_of = |x: f64, y: f64| {
+        let point = Vector2::new(x, y);
+        let walls = tree.locate(point);
+        walls
+            .iter()
+            .map(|wall| wall.in_front_point(&point))
+            .collect::<Vec<_>>()
+    };
+    let quadrants = [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)];
+    let regions = quadrants.map(|(x, y)| (tree.locate(Vector2::new(x, y)), sides_of(x, y)));
+    for (i, (walls, sides)) in regions.iter().enumerate() {
+        // the horizontal wall and the half of the vertical one on the
+        // quadrant's side of it
+        assert_eq!(walls.len(), 2);
+        let (x, y) = quadrants[i];
+        assert_eq!(tree.locate(Vector2::new(x * 0.5, y * 1.5)), *walls);
+        assert_eq!(sides_of(x * 0.5, y * 1.5), *sides);
+        for (other_walls, other_sides) in &regions[i + 1..] {
+            assert!(walls != other_walls || sides != other_sides);
+        }
+    }
+}
+
+#[test]
+fn line_of_sight_is_blocked_by_the_segment_only() {
+    let tree = tree(vec![wall((0.0, -1.0), (0.0, 1.0))]);
+    assert!(!tree.line_of_sight(Vector2::new(-1.0, 0.0), Vector2::new(1.0, 0.0)));
+    // crosses the wall's line above its end
+    assert!(tree.line_of_sight(Vector2::new(-1.0, 3.0), Vector2::new(1.0, 3.0)));
+}
+
+#[test]
+fn two_sided_walls_dont_block_line_of_sight() {
+    let (a, b) = (Vector2::new(-1.0, 0.0), Vector2::new(1.0, 0.0));
+    let mut portal = wall((0.0, -1.0), (0.0, 1.0));
+    portal.set_two_sided(true);
+    let behind = wall((2.0, -1.0), (2.0, 1.0));
+    let tree = tree(vec![portal, behind]);
+    assert!(tree.line_of_sight(a, b));
+    // the solid wall past the portal still blocks
+    assert!(!tree.line_of_sight(a, Vector2::new(3.0, 0.0)));
+}
+
+#[test]
+fn query_rect_keeps_walls_inside_and_crossing() {
+    let inside = wall((1.0, 1.0), (2.0, 1.0));
+    let crossing = wall((2.0, 2.0), (5.0, 2.0));
+    let outside = wall((5.0, 5.0), (6.0, 5.0));
+    // its bounds overlap the rectangle, but it passes beyond the corner
+    let diagonal = wall((2.0, 5.0), (5.0, 2.0));
+    let tree = tree(vec![outside, diagonal, inside, crossing]);
+    let mut found = tree.query_rect(Vector2::new(0.0, 0.0), Vector2::new(3.0, 3.0));
+    found.sort_by(|a, b| a.p1().x.total_cmp(&b.p1().x));
+    assert_eq!(found, [&inside, &crossing]);
+
+    // walls ending on a corner of the rectangle are found too
+    let touching = tree.query_rect(Vector2::new(5.0, 0.0), Vector2::new(7.0, 2.0));
+    assert_eq!(touching.len(), 2);
+    assert!(tree
+        .query_rect(Vector2::new(10.0, 10.0), Vector2::new(11.0, 11.0))
+        .is_empty());
+}
+
+#[test]
+fn the_leaf_polygon_of_a_room_is_the_room() {
+    let tree = room();
+    for (x, y) in [(2.0, 2.0), (0.5, 3.5), (3.9, 0.1)] {
+        let polygon = tree.leaf_polygon(Vector2::new(x, y));
+        assert_eq!(polygon.len(), 4);
+        // the four corners of the room, counterclockwise from any of them
+        let corners = [(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)];
+        let start = corners
+            .iter()
+            .position(|&(x, y)| polygon[0] == Vector2::new(x, y))
+            .unwrap();
+        for (i, corner) in polygon.iter().enumerate() {
+            let (x, y) = corners[(start + i) % 4];
+            assert_eq!(*corner, Vector2::new(x, y));
+        }
+    }
+}
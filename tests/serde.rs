@@ -0,0 +1,42 @@
+#![cfg(feature = "serde")]
+
+use bamn::{nalgebra::Vector2, BSPTree, Map};
+
+// a room with a pillar, with the pillar's walls first so building splits
+// some of the room's
+fn map() -> Map {
+    let square = |size: f64| {
+        vec![
+            Vector2::new(-size, -size),
+            Vector2::new(size, -size),
+            Vector2::new(size, size),
+            Vector2::new(-size, size),
+        ]
+    };
+    let map = Map::from_polygons(&square(4.0), &[square(1.0)]);
+    Map::from_walls(map.walls().iter().rev().copied().collect())
+}
+
+#[test]
+fn json_round_trips() {
+    let map = map();
+    let json = serde_json::to_string(&map).unwrap();
+    let read = serde_json::from_str::<Map>(&json).unwrap();
+    assert_eq!(read, map);
+    for (read, wall) in read.walls().iter().zip(map.walls()) {
+        assert_eq!(read.forward(), wall.forward());
+    }
+
+    let tree = map.generate_tree().unwrap();
+    assert!(tree.stats().splits > 0);
+    let json = serde_json::to_string(&tree).unwrap();
+    let read = serde_json::from_str::<BSPTree>(&json).unwrap();
+    assert_eq!(serde_json::to_string(&read).unwrap(), json);
+    for camera in [Vector2::new(2.0, 3.0), Vector2::new(-3.5, 0.0)] {
+        let (order, read_order) = (tree.get_render_order(camera), read.get_render_order(camera));
+        assert_eq!(read_order, order);
+        for (read, wall) in read_order.iter().zip(&order) {
+            assert_eq!(read.forward(), wall.forward());
+        }
+    }
+}
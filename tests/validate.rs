@@ -0,0 +1,82 @@
+use bamn::{nalgebra::Vector2, Map, MapError, Wall};
+
+fn wall(p1: (f64, f64), p2: (f64, f64)) -> Wall {
+    Wall::new(Vector2::new(p1.0, p1.1), Vector2::new(p2.0, p2.1))
+}
+
+fn validate(walls: Vec<Wall>) -> Result<(), Vec<MapError>> {
+    Map::from_walls(walls).validate()
+}
+
+#[test]
+fn a_closed_room_is_valid() {
+    let walls = vec![
+        wall((0.0, 0.0), (1.0, 0.0)),
+        wall((1.0, 0.0), (1.0, 1.0)),
+        wall((1.0, 1.0), (0.0, 0.0)),
+    ];
+    assert_eq!(validate(walls), Ok(()));
+}
+
+#[test]
+fn non_finite_walls_are_degenerate() {
+    let walls = vec![
+        wall((0.0, 0.0), (1.0, 0.0)),
+        wall((0.0, 2.0), (f64::NAN, 2.0)),
+    ];
+    assert_eq!(validate(walls), Err(vec![MapError::Degenerate { wall: 1 }]));
+}
+
+#[test]
+fn zero_length_walls_are_reported() {
+    let walls = vec![wall((3.0, 3.0), (3.0, 3.0)), wall((0.0, 0.0), (1.0, 0.0))];
+    assert_eq!(validate(walls), Err(vec![MapError::ZeroLength { wall: 0 }]));
+}
+
+#[test]
+fn duplicate_walls_are_reported_either_way_round() {
+    let walls = vec![
+        wall((0.0, 0.0), (1.0, 0.0)),
+        wall((0.0, 2.0), (1.0, 2.0)),
+        wall((1.0, 0.0), (0.0, 0.0)),
+    ];
+    let duplicate = MapError::Duplicate {
+        first: 0,
+        second: 2,
+    };
+    assert_eq!(validate(walls), Err(vec![duplicate]));
+}
+
+#[test]
+fn crossing_walls_are_reported() {
+    let walls = vec![
+        wall((0.0, 0.0), (2.0, 2.0)),
+        wall((5.0, 0.0), (6.0, 0.0)),
+        wall((0.0, 2.0), (2.0, 0.0)),
+        // touching at an endpoint isn't crossing
+        wall((2.0, 2.0), (3.0, 2.0)),
+    ];
+    let crossing = MapError::Intersecting {
+        first: 0,
+        second: 2,
+    };
+    assert_eq!(validate(walls), Err(vec![crossing]));
+}
+
+#[test]
+fn a_missing_wall_leaves_an_open_loop() {
+    let square = vec![
+        wall((0.0, 0.0), (2.0, 0.0)),
+        wall((2.0, 0.0), (2.0, 2.0)),
+        wall((2.0, 2.0), (0.0, 2.0)),
+        wall((0.0, 2.0), (0.0, 0.0)),
+    ];
+    assert!(Map::from_walls(square.clone()).find_open_loops().is_empty());
+
+    // without the right wall, the ends of the bottom and top walls dangle
+    let open = Map::from_walls(vec![square[0], square[2], square[3]]);
+    assert_eq!(
+        open.find_open_loops(),
+        [Vector2::new(2.0, 0.0), Vector2::new(2.0, 2.0)]
+    );
+}
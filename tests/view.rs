@@ -0,0 +1,240 @@
+use core::f64::consts::{FRAC_PI_2, PI};
+
+use bamn::{nalgebra::Vector2, BuildOptions, Camera, Map, Wall, WallVisibility};
+
+fn wall(p1: (f64, f64), p2: (f64, f64)) -> Wall {
+    Wall::new(Vector2::new(p1.0, p1.1), Vector2::new(p2.0, p2.1))
+}
+
+// at the origin looking along +x with a 90 degree field of view
+fn camera() -> Camera {
+    Camera::new(Vector2::zeros(), Vector2::x(), FRAC_PI_2)
+}
+
+#[test]
+fn fov_keeps_the_walls_inside_the_cone() {
+    // twelve short walls 30 degrees apart round the origin, facing it
+    let walls = (0..12)
+        .map(|i| {
+            let angle = f64::from(i) * PI / 6.0;
+            let (out, along) = (
+                Vector2::new(angle.cos(), angle.sin()),
+                Vector2::new(-angle.sin(), angle.cos()),
+            );
+            Wall::new(out * 5.0 + along * 0.5, out * 5.0 - along * 0.5)
+        })
+        .collect::<Vec<_>>();
+    let tree = Map::from_walls(walls.clone()).generate_tree().unwrap();
+
+    // the cone reaches 45 degrees either side of +x, taking in the walls at
+    // -30, 0 and 30 degrees
+    let mut seen = tree.get_render_order_fov(Vector2::zeros(), Vector2::x(), FRAC_PI_2);
+    seen.sort_by(|a, b| a.midpoint().y.total_cmp(&b.midpoint().y));
+    assert_eq!(seen, [walls[11], walls[0], walls[1]]);
+}
+
+#[test]
+fn projection_clips_the_end_behind_the_camera() {
+    let camera = camera();
+    // runs from behind the camera to 2 ahead of it, 1 to its left
+    let projected = wall((-2.0, 1.0), (2.0, 1.0)).project(&camera, 200).unwrap();
+    // the far end is at 45 degrees to the left, half way to the edge
+    assert!((projected.right_x - 50.0).abs() < 1e-9);
+    assert_eq!(projected.right_depth, 2.0);
+    // the near end is cut at the near plane, far off the left of the screen
+    assert!((projected.left_depth - camera.near).abs() < 1e-12);
+    assert!((projected.left_x - -9900.0).abs() < 1e-6);
+
+    assert_eq!(wall((-2.0, 1.0), (-1.0, -1.0)).project(&camera, 200), None);
+}
+
+#[test]
+fn clip_near_keeps_the_part_past_the_near_plane() {
+    let camera = camera();
+    let ahead = wall((2.0, 1.0), (2.0, -1.0));
+    let clipped = ahead.clip_near(&camera, 1.0).unwrap();
+    assert_eq!(clipped, ahead);
+    assert_eq!(clipped.forward(), ahead.forward());
+
+    assert!(wall((-2.0, 1.0), (-1.0, -1.0))
+        .clip_near(&camera, 1.0)
+        .is_none());
+    // closer than the near plane, though in front of the camera
+    assert!(wall((0.5, 1.0), (0.5, -1.0))
+        .clip_near(&camera, 1.0)
+        .is_none());
+
+    let straddling = wall((-1.0, 0.5), (3.0, 0.5));
+    assert_eq!(
+        straddling.clip_near(&camera, 1.0),
+        Some(wall((1.0, 0.5), (3.0, 0.5)))
+    );
+    let reversed = wall((3.0, 0.5), (-1.0, 0.5));
+    assert_eq!(
+        reversed.clip_near(&camera, 1.0),
+        Some(wall((3.0, 0.5), (1.0, 0.5)))
+    );
+}
+
+// a 2 by 2 room centered 5 along +x, with walls facing into it
+fn far_room() -> Vec<Wall> {
+    Map::from_polygon(&[
+        Vector2::new(4.0, -1.0),
+        Vector2::new(6.0, -1.0),
+        Vector2::new(6.0, 1.0),
+        Vector2::new(4.0, 1.0),
+    ])
+    .walls()
+    .to_vec()
+}
+
+#[test]
+fn a_solid_wall_hides_the_room_behind_it() {
+    // across the whole view, 2 ahead of the camera, and first so the room's
+    // walls don't split it
+    let mut solid = wall((2.0, -5.0), (2.0, 5.0));
+    let mut walls = vec![solid];
+    walls.extend(far_room());
+    let tree = Map::from_walls(walls.clone()).generate_tree().unwrap();
+    let visible = tree.visible_walls(Vector2::zeros(), Vector2::x(), FRAC_PI_2);
+    assert_eq!(visible, [&solid]);
+
+    // a two-sided wall is a portal, and the room's near wall, which hides
+    // the rest of it, shows through
+    solid.set_two_sided(true);
+    walls[0] = solid;
+    let near = walls.iter().find(|wall| wall.midpoint().x == 4.0).unwrap();
+    let tree = Map::from_walls(walls.clone()).generate_tree().unwrap();
+    let visible = tree.visible_walls(Vector2::zeros(), Vector2::x(), FRAC_PI_2);
+    assert_eq!(visible, [&solid, near]);
+}
+
+#[test]
+fn a_fully_occluded_wall_gets_an_empty_column_range() {
+    // the nearer wall fills the middle of the view, leaving its edges empty,
+    // and the farther one is narrower behind it
+    let near = wall((2.0, -1.0), (2.0, 1.0));
+    let far = wall((4.0, -1.0), (4.0, 1.0));
+    let tree = Map::from_walls(vec![near, far]).generate_tree().unwrap();
+    let columns = tree.render_columns(&camera(), 100);
+    assert_eq!(columns.len(), 2);
+    let (wall, filled) = &columns[0];
+    assert_eq!(*wall, near);
+    assert!(filled.start > 0 && filled.end < 100);
+    let (wall, hidden) = &columns[1];
+    assert_eq!(*wall, far);
+    assert!(hidden.is_empty());
+    assert!(filled.contains(&hidden.start));
+}
+
+#[test]
+fn render_regions_fills_a_pixel_per_three_bytes() {
+    let tree = Map::from_walls(vec![
+        wall((-2.0, 0.0), (2.0, 0.0)),
+        wall((0.0, -2.0), (0.0, 2.0)),
+    ])
+    .generate_tree()
+    .unwrap();
+    for (width, height) in [(64, 48), (1, 1), (7, 100), (0, 10), (10, 0)] {
+        let image = tree.render_regions(width, height);
+        assert_eq!(image.len(), width as usize * height as usize * 3);
+    }
+
+    // the four quadrants of the plus, and the black walls
+    let image = tree.render_regions(64, 64);
+    let mut colors = image.chunks(3).collect::<Vec<_>>();
+    colors.sort();
+    colors.dedup();
+    assert_eq!(colors.len(), 5);
+    assert!(colors.contains(&&[0, 0, 0][..]));
+}
+
+#[test]
+fn a_convex_leaf_gets_one_color() {
+    // a square room, kept as one leaf inside its first wall
+    let corners = [(-2.0, -2.0), (2.0, -2.0), (2.0, 2.0), (-2.0, 2.0)];
+    let walls = (0..4)
+        .map(|i| wall(corners[i], corners[(i + 1) % 4]))
+        .collect();
+    let tree = Map::from_walls(walls)
+        .generate_tree_with_options(BuildOptions {
+            convex_leaves: true,
+            ..BuildOptions::default()
+        })
+        .unwrap();
+    assert_eq!(tree.stats().nodes, 1);
+
+    // the room is one region, different from the side behind the wall it
+    // was split on
+    let image = tree.render_regions(32, 40);
+    let pixel = |column: usize, row: usize| &image[(row * 32 + column) * 3..][..3];
+    let inside = pixel(16, 20);
+    for row in 5..35 {
+        for column in 1..31 {
+            assert_eq!(pixel(column, row), inside);
+        }
+    }
+    assert_ne!(pixel(16, 39), inside);
+}
+
+#[test]
+fn walls_by_angle_go_round_from_behind_on_the_right() {
+    // walls 30 degrees apart all round the camera, in shuffled order
+    let walls = [4, 9, 0, 7, 2, 11, 6, 1, 10, 3, 8, 5]
+        .map(|i| {
+            let angle = f64::from(i) * PI / 6.0;
+            let (out, along) = (
+                Vector2::new(angle.cos(), angle.sin()),
+                Vector2::new(-angle.sin(), angle.cos()),
+            );
+            Wall::new(out * 5.0 + along * 0.5, out * 5.0 - along * 0.5)
+        })
+        .to_vec();
+    let tree = Map::from_walls(walls).generate_tree().unwrap();
+    let found = tree.walls_by_angle(Vector2::zeros(), Vector2::x(), 2.0 * PI);
+    assert_eq!(found.len(), 12);
+    assert!(found.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    for (angle, wall) in &found {
+        // each angle points at the middle of its wall
+        let middle = wall.midpoint() / 5.0;
+        assert!((Vector2::new(angle.cos(), angle.sin()) - middle).norm() < 1e-9);
+        assert!(angle.abs() <= PI);
+    }
+
+    // a narrower cone keeps the walls inside it, still in order
+    let found = tree.walls_by_angle(Vector2::zeros(), Vector2::x(), FRAC_PI_2);
+    let angles = found.iter().map(|(angle, _)| *angle).collect::<Vec<_>>();
+    assert_eq!(angles.len(), 3);
+    for (angle, expected) in angles.iter().zip([-PI / 6.0, 0.0, PI / 6.0]) {
+        assert!((angle - expected).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn the_trace_blames_hidden_walls_on_the_walls_in_front() {
+    let near = wall((2.0, -1.0), (2.0, 1.0));
+    // wider than `near`, so its ends show past it
+    let wide = wall((4.0, -3.0), (4.0, 3.0));
+    // narrower than `near`, and hidden behind it
+    let hidden = wall((5.0, -1.0), (5.0, 1.0));
+    let behind = wall((-2.0, 1.0), (-2.0, -1.0));
+    let tree = Map::from_walls(vec![near, wide, hidden, behind])
+        .generate_tree()
+        .unwrap();
+    let index = |wall: Wall| tree.iter().position(|found| *found == wall).unwrap();
+
+    let trace = tree.visibility_trace(&camera());
+    assert_eq!(trace.walls().len(), 4);
+    let near_trace = trace.wall(index(near));
+    assert_eq!(near_trace.visibility, WallVisibility::Visible);
+    assert!(near_trace.occluders.is_empty());
+    let wide_trace = trace.wall(index(wide));
+    assert_eq!(wide_trace.visibility, WallVisibility::PartiallyVisible);
+    assert_eq!(wide_trace.occluders, [index(near)]);
+    let hidden_trace = trace.wall(index(hidden));
+    assert_eq!(hidden_trace.visibility, WallVisibility::Occluded);
+    assert!(hidden_trace.occluders.contains(&index(near)));
+    let behind_trace = trace.wall(index(behind));
+    assert_eq!(behind_trace.visibility, WallVisibility::OutsideFrustum);
+    assert!(behind_trace.occluders.is_empty());
+}
@@ -0,0 +1,198 @@
+use bamn::{nalgebra::Vector2, Camera, Map, Wall};
+
+fn wall(p1: (f64, f64), p2: (f64, f64)) -> Wall {
+    Wall::new(Vector2::new(p1.0, p1.1), Vector2::new(p2.0, p2.1))
+}
+
+#[test]
+fn intersection_segment_needs_both_segments_to_cross() {
+    let a = wall((0.0, 0.0), (2.0, 0.0));
+    let b = wall((1.0, -1.0), (1.0, 1.0));
+    assert_eq!(a.intersection_segment(&b), Some(Vector2::new(1.0, 0.0)));
+
+    // the line through `c` crosses `a` at (3, 0), past its end
+    let c = wall((3.0, -1.0), (3.0, 1.0));
+    assert_eq!(a.intersection_segment(&c), None);
+    assert_eq!(c.intersection_segment(&a), None);
+    // `intersection` only bounds the wall itself, not the plane
+    assert_eq!(c.intersection(&a), Some(Vector2::new(3.0, 0.0)));
+    assert_eq!(a.intersection(&c), None);
+}
+
+#[test]
+fn spliced_walls_keep_their_texture() {
+    let mut a = wall((0.0, 0.0), (4.0, 0.0));
+    a.set_texture(7);
+    let (left, right) = a.splice(Vector2::new(1.0, 0.0));
+    assert_eq!((left.texture(), right.texture()), (7, 7));
+
+    // and so do the pieces a tree cuts it into
+    let cut = wall((2.0, -1.0), (2.0, 1.0));
+    let tree = Map::from_walls(vec![cut, a]).generate_tree().unwrap();
+    let order = tree.get_render_order(Vector2::new(1.0, 2.0));
+    let pieces = order.iter().filter(|wall| wall.p1().y == 0.0);
+    assert_eq!(
+        pieces.map(|wall| wall.texture()).collect::<Vec<_>>(),
+        [7, 7]
+    );
+}
+
+#[test]
+fn u_offsets_continue_across_splices() {
+    let a = wall((0.0, 0.0), (10.0, 0.0));
+    let (first, rest) = a.splice(Vector2::new(4.0, 0.0));
+    let (second, third) = rest.splice(Vector2::new(7.0, 0.0));
+    let pieces = [first, second, third];
+    assert_eq!(pieces.map(|wall| wall.u_offset()), [0.0, 4.0, 7.0]);
+    // each piece starts where the one before it ends
+    for pair in pieces.windows(2) {
+        assert_eq!(pair[0].u_offset() + pair[0].length(), pair[1].u_offset());
+    }
+}
+
+#[test]
+fn near_parallel_walls_at_large_coordinates() {
+    let (x, y) = (1e7, 1e7);
+    let a = wall((x, y), (x + 1000.0, y));
+    // rises 2 in 1000, crossing `a` half way along
+    let b = wall((x, y - 1.0), (x + 1000.0, y + 1.0));
+    let point = a.intersection_segment(&b).unwrap();
+    assert!((point - Vector2::new(x + 500.0, y)).norm() < 1e-6);
+    assert!((b.intersection(&a).unwrap() - point).norm() < 1e-6);
+
+    // parallel, and parallel to within rounding of the coordinates
+    let c = wall((x, y + 1.0), (x + 1000.0, y + 1.0));
+    assert_eq!(a.intersection(&c), None);
+    let d = wall((x, y + 1.0), (x + 1000.0, y + 1.0 + 1e-9));
+    assert_eq!(a.intersection(&d), None);
+
+    // `b` is cut where it crosses `a`, and nowhere wild
+    let tree = Map::from_walls(vec![a, b]).generate_tree().unwrap();
+    assert_eq!(tree.iter().count(), 3);
+    let ends = [a.p1(), a.p2(), b.p1(), b.p2(), point];
+    for piece in tree.iter() {
+        for end in [piece.p1(), piece.p2()] {
+            assert!(ends.iter().any(|known| (end - known).norm() < 1e-6));
+        }
+    }
+}
+
+#[test]
+fn same_segment_ignores_direction() {
+    let a = wall((0.0, 0.0), (2.0, 1.0));
+    let reversed = wall((2.0, 1.0), (0.0, 0.0));
+    assert!(a.same_segment(&reversed) && reversed.same_segment(&a));
+    // but equality keeps the direction, which decides the facing
+    assert_ne!(a, reversed);
+    assert_eq!(a, wall((0.0, 0.0), (2.0, 1.0)));
+    assert!(!a.same_segment(&wall((0.0, 0.0), (2.0, 2.0))));
+}
+
+#[test]
+fn forward_is_as_long_as_the_wall() {
+    let a = wall((0.0, 0.0), (3.0, 4.0));
+    assert_eq!(a.forward().norm(), 5.0);
+    assert_eq!(a.forward(), Vector2::new(-4.0, 3.0));
+
+    let (first, second) = a.splice(a.point_at(0.2));
+    for piece in [first, second] {
+        assert!((piece.forward().norm() - piece.length()).abs() < 1e-12);
+        assert!((piece.normal() - a.normal()).norm() < 1e-12);
+        assert_eq!(piece.winding(), a.winding());
+    }
+
+    let camera = Camera::new(Vector2::zeros(), Vector2::new(3.0, 4.0), 1.0);
+    let clipped = a.clip_near(&camera, 1.0).unwrap();
+    assert!((clipped.length() - 4.0).abs() < 1e-12);
+    assert!((clipped.forward().norm() - 4.0).abs() < 1e-12);
+
+    let merged = Map::merge_collinear(&[second, first]);
+    assert_eq!(merged, [a]);
+    assert!((merged[0].forward() - a.forward()).norm() < 1e-12);
+}
+
+#[test]
+fn distance_to_point_clamps_to_the_ends() {
+    let a = wall((0.0, 0.0), (4.0, 0.0));
+    // beside the middle the distance is straight to the wall
+    assert_eq!(a.distance_to_point(Vector2::new(2.0, 3.0)), 3.0);
+    assert_eq!(a.distance_to_point(Vector2::new(1.0, -0.5)), 0.5);
+    // past either end it is to the endpoint
+    assert_eq!(a.distance_to_point(Vector2::new(7.0, 4.0)), 5.0);
+    assert_eq!(a.distance_to_point(Vector2::new(-3.0, -4.0)), 5.0);
+
+    let b = wall((6.0, 1.0), (6.0, 3.0));
+    let map = Map::from_walls(vec![a, b]);
+    let tree = map.generate_tree().unwrap();
+    // nearest to the middle of `b`, then to the end of `a`
+    for (point, nearest, distance) in [((5.0, 2.0), b, 1.0), ((5.0, 0.0), a, 1.0)] {
+        let point = Vector2::new(point.0, point.1);
+        let found = map.nearest_wall(point).unwrap();
+        assert_eq!((found.0, *found.1), (distance, nearest));
+        let found = tree.nearest_wall(point).unwrap();
+        assert_eq!((found.0, *found.1), (distance, nearest));
+    }
+}
+
+#[test]
+fn pieces_keep_the_id_of_their_wall() {
+    let cut = wall((2.0, -1.0), (2.0, 1.0));
+    let long = wall((0.0, 0.0), (4.0, 0.0));
+    let mut map = Map::from_walls(vec![cut, long]);
+    map.push_wall(Vector2::new(0.0, 3.0), Vector2::new(4.0, 3.0));
+    assert_eq!(
+        map.walls().iter().map(Wall::id).collect::<Vec<_>>(),
+        [0, 1, 2]
+    );
+
+    let tree = map.generate_tree().unwrap();
+    let pieces = tree.iter().filter(|piece| piece.p1().y == 0.0);
+    assert_eq!(pieces.map(Wall::id).collect::<Vec<_>>(), [1, 1]);
+
+    // merged walls are numbered after the map's own
+    let other = map.clone();
+    map.merge(&other, Vector2::new(10.0, 0.0));
+    assert_eq!(
+        map.walls().iter().map(Wall::id).collect::<Vec<_>>(),
+        [0, 1, 2, 3, 4, 5]
+    );
+}
+
+#[test]
+fn light_survives_a_splitting_build() {
+    let mut a = wall((0.0, 0.0), (4.0, 0.0));
+    a.set_light(0.25);
+    let cut = wall((2.0, -1.0), (2.0, 1.0));
+    let tree = Map::from_walls(vec![cut, a]).generate_tree().unwrap();
+    assert_eq!(tree.stats().splits, 1);
+    let pieces = tree.iter().filter(|wall| wall.p1().y == 0.0);
+    assert_eq!(pieces.map(Wall::light).collect::<Vec<_>>(), [0.25, 0.25]);
+    assert_eq!(cut.light(), 1.0);
+}
+
+#[test]
+fn point_at_runs_from_p1_to_p2() {
+    let a = wall((1.0, 2.0), (5.0, -4.0));
+    assert_eq!(a.point_at(0.0), a.p1());
+    assert_eq!(a.point_at(0.5), Vector2::new(3.0, -1.0));
+    assert_eq!(a.point_at(0.5), a.midpoint());
+    assert_eq!(a.point_at(1.0), a.p2());
+}
+
+#[test]
+fn point_at_the_intersection_t_is_the_intersection() {
+    let plane = wall((0.0, 1.0), (1.0, 2.0));
+    for a in [
+        wall((0.0, 4.0), (4.0, 0.0)),
+        wall((3.0, -1.0), (-2.0, 5.0)),
+        wall((-1.0, 3.0), (2.0, 0.5)),
+    ] {
+        let t = a.intersection_t(&plane).unwrap();
+        let point = a.intersection(&plane).unwrap();
+        assert_eq!(a.point_at(t), point);
+        assert!(plane.signed_distance(point).abs() < 1e-12);
+        assert!(((point - a.p1()).norm() - t * a.length()).abs() < 1e-12);
+    }
+    // parallel to the plane, so there is no crossing
+    assert_eq!(wall((0.0, 0.0), (2.0, 2.0)).intersection_t(&plane), None);
+}